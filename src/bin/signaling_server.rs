@@ -0,0 +1,224 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::{SinkExt, StreamExt};
+use matchbox_protocol::{JsonPeerEvent, JsonPeerRequest, PeerId};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{Request, Response},
+    http::Uri,
+    Message,
+};
+use uuid::Uuid;
+
+// the game connects to this like a matchbox_server, both use the matchbox_protocol types
+const DEFAULT_ADDR: &str = "0.0.0.0:3536";
+
+// "ws://host/quick_match?next=2" => RoomId { id: "quick_match", next: Some(2) }
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct RoomId {
+    id: String,
+    next: Option<usize>,
+}
+
+impl RoomId {
+    fn from_uri(uri: &Uri) -> Self {
+        let next = uri.query().and_then(|query| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == "next")
+                .and_then(|(_, value)| value.parse().ok())
+        });
+        Self {
+            id: uri.path().trim_matches('/').to_string(),
+            next,
+        }
+    }
+}
+
+struct Peer {
+    room: RoomId,
+    // set once a `next` room filled up with this peer in it
+    match_id: Option<u64>,
+    sender: mpsc::UnboundedSender<Message>,
+}
+
+#[derive(Default)]
+struct ServerState {
+    peers: HashMap<PeerId, Peer>,
+    rooms: HashMap<RoomId, HashSet<PeerId>>,
+    // full `next` groups leave the room for the next peers but still hear who leaves
+    matches: HashMap<u64, HashSet<PeerId>>,
+    next_match_id: u64,
+}
+
+impl ServerState {
+    // returns the peers already waiting in the room
+    fn add_peer(&mut self, peer_id: PeerId, peer: Peer) -> Vec<PeerId> {
+        let room = peer.room.clone();
+        self.peers.insert(peer_id, peer);
+        let waiting_peers = self.rooms.entry(room.clone()).or_default();
+        let prev_peers = waiting_peers.iter().copied().collect();
+        waiting_peers.insert(peer_id);
+        // the room is full, the next peer starts a new match
+        if room.next.map_or(false, |x| waiting_peers.len() >= x) {
+            let group = std::mem::take(waiting_peers);
+            self.rooms.remove(&room);
+            let match_id = self.next_match_id;
+            self.next_match_id += 1;
+            for id in group.iter() {
+                if let Some(peer) = self.peers.get_mut(id) {
+                    peer.match_id = Some(match_id);
+                }
+            }
+            self.matches.insert(match_id, group);
+        }
+        prev_peers
+    }
+
+    // returns the peers still in the same room or match
+    fn remove_peer(&mut self, peer_id: PeerId) -> Vec<PeerId> {
+        let Some(peer) = self.peers.remove(&peer_id) else {
+            return Vec::new();
+        };
+        if let Some(match_id) = peer.match_id {
+            let Some(group) = self.matches.get_mut(&match_id) else {
+                return Vec::new();
+            };
+            group.remove(&peer_id);
+            let remaining_peers = group.iter().copied().collect();
+            if group.is_empty() {
+                self.matches.remove(&match_id);
+            }
+            return remaining_peers;
+        }
+        let Some(waiting_peers) = self.rooms.get_mut(&peer.room) else {
+            return Vec::new();
+        };
+        if !waiting_peers.remove(&peer_id) {
+            return Vec::new();
+        }
+        let remaining_peers = waiting_peers.iter().copied().collect();
+        if waiting_peers.is_empty() {
+            self.rooms.remove(&peer.room);
+        }
+        remaining_peers
+    }
+
+    fn send(&self, peer_id: PeerId, event: &JsonPeerEvent) {
+        let Some(peer) = self.peers.get(&peer_id) else {
+            eprintln!("unknown peer {:?}", peer_id);
+            return;
+        };
+        match serde_json::to_string(event) {
+            Ok(text) => {
+                let _ = peer.sender.send(Message::Text(text));
+            }
+            Err(err) => eprintln!("failed to serialize {:?}: {}", event, err),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let addr = env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let listener = TcpListener::bind(&addr)
+        .await
+        .unwrap_or_else(|err| panic!("failed to bind {}: {}", addr, err));
+    // the bound address, e.g. the real port when started on port 0
+    let local_addr = listener.local_addr().expect("listener has no address");
+    println!("signaling server listening on {}", local_addr);
+
+    let state = Arc::new(Mutex::new(ServerState::default()));
+    while let Ok((stream, remote_addr)) = listener.accept().await {
+        tokio::spawn(handle_connection(state.clone(), stream, remote_addr));
+    }
+}
+
+async fn handle_connection(
+    state: Arc<Mutex<ServerState>>,
+    stream: TcpStream,
+    remote_addr: SocketAddr,
+) {
+    let mut room = None;
+    let callback = |request: &Request, response: Response| {
+        room = Some(RoomId::from_uri(request.uri()));
+        Ok(response)
+    };
+    let ws_stream = match tokio_tungstenite::accept_hdr_async(stream, callback).await {
+        Ok(ws_stream) => ws_stream,
+        Err(err) => {
+            eprintln!("websocket handshake with {} failed: {}", remote_addr, err);
+            return;
+        }
+    };
+    let Some(room) = room else {
+        return;
+    };
+
+    let peer_id = PeerId(Uuid::new_v4());
+    let (mut ws_sink, mut ws_source) = ws_stream.split();
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let writer = tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            if ws_sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    println!("{:?} joined {:?} from {}", peer_id, room, remote_addr);
+    {
+        let mut state = state.lock().unwrap();
+        let prev_peers = state.add_peer(
+            peer_id,
+            Peer {
+                room,
+                match_id: None,
+                sender,
+            },
+        );
+        state.send(peer_id, &JsonPeerEvent::IdAssigned(peer_id));
+        for prev_peer in prev_peers {
+            state.send(prev_peer, &JsonPeerEvent::NewPeer(peer_id));
+        }
+    }
+
+    while let Some(Ok(message)) = ws_source.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        match serde_json::from_str::<JsonPeerRequest>(&text) {
+            Ok(JsonPeerRequest::Signal { receiver, data }) => {
+                let event = JsonPeerEvent::Signal {
+                    sender: peer_id,
+                    data,
+                };
+                state.lock().unwrap().send(receiver, &event);
+            }
+            Ok(JsonPeerRequest::KeepAlive) => {}
+            Err(err) => eprintln!("invalid request from {:?}: {}", peer_id, err),
+        }
+    }
+
+    println!("{:?} left", peer_id);
+    {
+        let mut state = state.lock().unwrap();
+        for remaining_peer in state.remove_peer(peer_id) {
+            state.send(remaining_peer, &JsonPeerEvent::PeerLeft(peer_id));
+        }
+    }
+    writer.abort();
+}
@@ -1,5 +1,6 @@
 pub mod animation;
 pub mod fake_brick;
+pub mod garbage;
 pub mod nails_brick;
 pub mod normal_brick;
 pub mod spring_brick;
@@ -0,0 +1,7 @@
+use bevy::prelude::*;
+
+// a brick another player sent in online versus, only its target can touch it
+#[derive(Component)]
+pub struct Garbage {
+    pub target: usize,
+}
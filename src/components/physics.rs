@@ -12,5 +12,6 @@ pub struct LastCollisions {
     pub collisions: Vec<Collision>,
 }
 
-#[derive(Component, Deref, DerefMut, Clone, Copy)]
+#[derive(Component, Reflect, Default, Deref, DerefMut, Clone, Copy)]
+#[reflect(Component)]
 pub struct Velocity(pub Vec2);
@@ -14,6 +14,8 @@ pub struct PlayerBundle {
     pub player: Player,
     pub score: PlayerScore,
     pub health: Health,
+    pub survival_ticks: SurvivalTicks,
+    pub garbage_attack: GarbageAttack,
 
     pub spirtes: SpriteSheetBundle,
     pub animations: PlayerAnimations,
@@ -35,6 +37,8 @@ impl PlayerBundle {
             player: Player { handle },
             score: PlayerScore { score: 0 },
             health: Health::new_player_health(),
+            survival_ticks: SurvivalTicks::default(),
+            garbage_attack: GarbageAttack::default(),
 
             spirtes: SpriteSheetBundle {
                 texture_atlas: player_assets.sprite_sheet.clone(),
@@ -73,7 +77,12 @@ pub struct Player {
     pub handle: usize,
 }
 
-#[derive(Component, Default, Clone)]
+// a recorded run drawn next to the player, it has no collider so nothing touches it
+#[derive(Component)]
+pub struct Ghost {}
+
+#[derive(Component, Reflect, Default, Clone)]
+#[reflect(Component)]
 pub struct PlayerScore {
     pub score: i32,
 }
@@ -157,6 +166,7 @@ impl Default for PlayerAnimations {
 }
 
 #[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Health {
     pub(crate) value: i32,
     pub(crate) max: i32,
@@ -194,13 +204,42 @@ pub struct Grounded {}
 #[component(storage = "SparseSet")]
 pub struct Flying {}
 
-#[derive(Component)]
+// rolled back online, garbage attacks reset on it
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
 #[component(storage = "SparseSet")]
 pub struct Damaging {}
 
-#[derive(Component)]
+// both are rolled back online, a mispredicted death or revive is undone with the health
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
 pub struct Dead {}
 
+// a dead co-op player lying on a brick, a teammate landing next to them revives them
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Downed {}
+
+// ticks alive while someone else was too, decides the death order and depth in versus,
+// rolled back with the rest of the player so mispredicted deaths don't stick
+#[derive(Component, Reflect, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct SurvivalTicks(pub u32);
+
+// online versus, counts toward the next garbage sent and the garbage waiting to be
+// injected into this player's rows, see `garbage_systems`
+#[derive(Component, Reflect, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct GarbageAttack {
+    pub landing_streak: u32,
+    pub spring_combo: u32,
+    // picks the next opponent in turn
+    pub sent: u32,
+    pub pending: u32,
+    // seeds where the next garbage brick goes
+    pub received: u32,
+}
+
 #[derive(Component)]
 #[component(storage = "SparseSet")]
 pub struct Jumping {}
@@ -221,7 +260,8 @@ impl Default for JumpingTimer {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct DamagingTimer {
     pub(crate) timer: Timer,
 }
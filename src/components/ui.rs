@@ -1,5 +1,9 @@
+use std::net::SocketAddr;
+
 use bevy::{prelude::*, time::Stopwatch};
 
+use crate::resources::{key_bindings::InputAction, GameRules};
+
 #[derive(Component)]
 pub struct InGameUi {}
 
@@ -9,20 +13,42 @@ pub struct InGameResultMenuUi {}
 #[derive(Component)]
 pub struct InGameResultMenuScoreText {}
 
+#[derive(Component)]
+pub struct InGameResultMenuMessageText {}
+
 #[derive(Component)]
 pub struct InGameResultBackToMainMenuButton {}
 
 #[derive(Component)]
 pub struct InGameResultPlayAgainButton {}
 
+#[derive(Component)]
+pub struct RematchStatusText {}
+
+#[derive(Component, Default)]
+pub struct InGameResultMenuPlayerScoreText {
+    pub handle: usize,
+}
+
 #[derive(Component)]
 pub struct ScoreText {}
 
+#[derive(Component)]
+pub struct NetworkStatusText {}
+
+#[derive(Component)]
+pub struct NetworkDiagnosticsText {}
+
 #[derive(Component, Default)]
 pub struct PlayerHealthText {
     pub handle: usize,
 }
 
+#[derive(Component, Default)]
+pub struct PlayerScoreText {
+    pub handle: usize,
+}
+
 #[derive(Component)]
 pub struct MainMenuUi {}
 
@@ -30,7 +56,41 @@ pub struct MainMenuUi {}
 pub struct SinglePlayButton {}
 
 #[derive(Component)]
-pub struct OnlinePlayButton {}
+pub struct RaceGhostButton {}
+
+#[derive(Component)]
+pub struct LocalPlayersButton {
+    pub num_players: usize,
+}
+
+#[derive(Component)]
+pub struct OnlinePlayersButton {
+    pub num_players: usize,
+}
+
+// for local and online games alike
+#[derive(Component)]
+pub struct RulesButton {
+    pub rules: GameRules,
+}
+
+#[derive(Component)]
+pub struct QuickMatchButton {}
+
+#[derive(Component)]
+pub struct CreateRoomButton {}
+
+#[derive(Component)]
+pub struct JoinRoomButton {}
+
+#[derive(Component)]
+pub struct WatchRoomButton {}
+
+#[derive(Component)]
+pub struct LanPlayButton {}
+
+#[derive(Component)]
+pub struct SettingsButton {}
 
 #[derive(Component)]
 pub struct QuitButton {}
@@ -41,6 +101,27 @@ pub struct MatchmakingUi {}
 #[derive(Component)]
 pub struct MatchingElapsedText {}
 
+#[derive(Component)]
+pub struct MatchmakingRoomCodeText {}
+
+#[derive(Component)]
+pub struct MatchmakingErrorText {}
+
+#[derive(Component)]
+pub struct MatchmakingPlayerList {}
+
+#[derive(Component)]
+pub struct MatchmakingCountdownText {}
+
+#[derive(Component)]
+pub struct MatchmakingReadyButton {}
+
+#[derive(Component)]
+pub struct MatchmakingReadyText {}
+
+#[derive(Component)]
+pub struct MatchmakingRetryButton {}
+
 #[derive(Component)]
 pub struct MatchingTimer {
     pub elapsed_timer: Stopwatch,
@@ -49,6 +130,44 @@ pub struct MatchingTimer {
 #[derive(Component)]
 pub struct BackMainMenuButton {}
 
+#[derive(Component)]
+pub struct JoinRoomUi {}
+
+#[derive(Component)]
+pub struct RoomCodeInputText {}
+
+#[derive(Component)]
+pub struct RoomCodeErrorText {}
+
+#[derive(Component)]
+pub struct JoinRoomConfirmButton {}
+
+#[derive(Component)]
+pub struct SettingsUi {}
+
+#[derive(Component)]
+pub struct LanLobbyUi {}
+
+#[derive(Component)]
+pub struct LanHostButton {}
+
+#[derive(Component)]
+pub struct LanHostList {}
+
+#[derive(Component)]
+pub struct LanJoinHostButton {
+    pub addr: SocketAddr,
+}
+
+#[derive(Component)]
+pub struct LanAddressInputText {}
+
+#[derive(Component)]
+pub struct LanConnectButton {}
+
+#[derive(Component)]
+pub struct LanStatusText {}
+
 pub struct Styles {
     pub normal_button: Style,
     pub normal_button_medium: Style,
@@ -77,3 +196,29 @@ pub const GLOBAL_STYLES: Styles = Styles {
     hovered_button_color: Color::rgb(0.25, 0.25, 0.25),
     pressed_button_color: Color::rgb(0.35, 0.75, 0.35),
 };
+
+#[derive(Component)]
+pub struct ControlsButton {}
+
+#[derive(Component)]
+pub struct ControlsUi {}
+
+#[derive(Component)]
+pub struct RebindKeyButton {
+    pub local_player: usize,
+    pub action: InputAction,
+}
+
+#[derive(Component)]
+pub struct RebindKeyText {
+    pub local_player: usize,
+    pub action: InputAction,
+}
+
+#[derive(Component)]
+pub struct ResetKeyBindingsButton {}
+
+#[derive(Component)]
+pub struct VirtualMoveButton {
+    pub move_x: f32,
+}
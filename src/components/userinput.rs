@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 
 #[derive(Component, Reflect, Default)]
+#[reflect(Component)]
 pub struct Userinput {
     pub move_accelection: Vec2,
 }
\ No newline at end of file
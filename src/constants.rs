@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 use bevy_ggrs::ggrs;
-use bevy_matchbox::prelude::PeerId;
+
+use crate::utils::{input_utils::PlayerInput, network_utils::NetAddress};
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum BrickType {
@@ -11,7 +12,18 @@ pub enum BrickType {
     Spring,
 }
 
-pub const PHYSICS_DELTA: f64 = 1.0 / 60.0;
+// ticks per second, offline and online, the timers in ticks are written for it
+pub const SIMULATION_FPS: usize = 60;
+pub const PHYSICS_DELTA: f64 = 1.0 / SIMULATION_FPS as f64;
+// how long a rematch waits without a session, see `start_rematch_system`
+pub const GGRS_RESET_TICKS: usize = 2;
+
+pub const DEFAULT_STAGE_SEED: u64 = 5;
+// every peer simulates the same stage whatever their window, the camera fits it instead
+pub const ONLINE_STAGE_WIDTH: f32 = 540.0;
+pub const ONLINE_STAGE_HEIGHT: f32 = 960.0;
+// bricks rise a pixel per tick, so a floor passes every `BRICK_ROW_SPACING` ticks
+pub const BRICK_ROW_SPACING: i32 = 55;
 
 pub const IN_GAME_UI_APP_BAR_HEIGHT: f32 = 32.0;
 
@@ -21,24 +33,83 @@ pub const WALL_HEIGHT: f32 = 960.0 - IN_GAME_UI_APP_BAR_HEIGHT;
 pub const CELLING_WIDTH: f32 = 540.0 - (WALL_WIDTH * 2.0);
 pub const CELLING_HEIGHT: f32 = 16.0;
 
-pub const INPUT_LEFT: u8 = 1 << 0;
-pub const INPUT_RIGHT: u8 = 1 << 1;
+pub const MIN_ONLINE_PLAYERS: usize = 2;
+pub const MAX_ONLINE_PLAYERS: usize = 4;
+
+pub const GGRS_CHANNEL: usize = 0;
+pub const RELIABLE_CHANNEL: usize = 1;
+
+pub const SPECTATOR_MAX_FRAMES_BEHIND: usize = 10;
+pub const SPECTATOR_CATCHUP_SPEED: usize = 2;
+
+// seconds between everyone being ready and the match starting
+pub const LOBBY_COUNTDOWN: f32 = 3.0;
+pub const NICKNAME_MAX_LEN: usize = 16;
+
+pub const LAN_PORT: u16 = 7000;
+pub const LAN_DISCOVERY_PORT: u16 = 7001;
+pub const LAN_ANNOUNCE_INTERVAL: f32 = 1.0;
+pub const LAN_JOIN_INTERVAL: f32 = 0.5;
+pub const LAN_HOST_TIMEOUT: f32 = 3.0;
+// the host sends the start message again until every joiner acknowledged it
+pub const LAN_START_RESEND_INTERVAL: f32 = 0.2;
+pub const LAN_START_TIMEOUT: f32 = 3.0;
+// nobody acknowledges a cancel, it is sent a few times instead
+pub const LAN_CANCEL_REPEAT: usize = 3;
+// ggrs packets waiting for the session to read them, e.g. between a match and its rematch
+pub const LAN_MAX_GGRS_BACKLOG: usize = 1024;
+
+pub const NETWORK_DIAGNOSTICS_KEY: KeyCode = KeyCode::F3;
+
+// the move axis is quantized to -127..=127 so it fits in a byte
+pub const INPUT_AXIS_MAX: i8 = 127;
+
+pub const MAX_LOCAL_PLAYERS: usize = 2;
+// relative to the working directory
+pub const KEY_BINDINGS_PATH: &str = "key_bindings.toml";
+
+pub const GAMEPAD_DEAD_ZONE: f32 = 0.15;
+// pixels a touch has to be dragged from where it started for full speed
+pub const TOUCH_DRAG_RANGE: f32 = 80.0;
+// shorter drags fall back to holding the left or right half of the screen
+pub const TOUCH_DRAG_DEAD_ZONE: f32 = 12.0;
+
+pub const REPLAY_MAGIC: [u8; 4] = *b"NSRP";
+pub const REPLAY_VERSION: u16 = 4;
+// every finished offline run is saved here, relative to the working directory
+pub const LAST_REPLAY_PATH: &str = "last_run.nsreplay";
+// the best single player run, raced as a ghost unless `--ghost <file>` is given
+pub const BEST_REPLAY_PATH: &str = "best_run.nsreplay";
+pub const GHOST_OPACITY: f32 = 0.4;
+// how far from a downed co-op teammate a player can land and still revive them
+pub const REVIVE_DISTANCE: f32 = 48.0;
+// online versus, landings in a row without getting hurt that send one garbage brick
+pub const GARBAGE_LANDING_STREAK: u32 = 8;
+// springs in a row from which every spring sends one garbage brick
+pub const GARBAGE_SPRING_COMBO: u32 = 2;
+// how someone else's garbage looks to the local player
+pub const GARBAGE_OPACITY: f32 = 0.3;
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States)]
 pub enum AppState {
     #[default]
     AssetLoading,
     MainMenu,
+    JoinRoom,
     Matchmaking,
+    LanLobby,
+    Settings,
+    Controls,
     InGame,
+    Rematch,
 }
 
 pub struct GgrsConfig;
 
 impl ggrs::Config for GgrsConfig {
-    // 4-directions + fire fits easily in a single byte
-    type Input = u8;
+    // analog move axis + buttons, two bytes
+    type Input = PlayerInput;
     type State = u8;
-    // Matchbox' WebRtcSocket addresses are called `PeerId`s
-    type Address = PeerId;
+    // matchbox `PeerId`s online, socket addresses on lan
+    type Address = NetAddress;
 }
@@ -9,8 +9,14 @@ use bevy_asset_loader::prelude::*;
 use bevy_ggrs::{GGRSPlugin, GGRSSchedule};
 use bevy_kira_audio::AudioPlugin;
 
-use components::{player::Health, userinput::Userinput};
-use constants::{AppState, GgrsConfig, PHYSICS_DELTA};
+use components::{
+    physics::Velocity,
+    player::{
+        Damaging, DamagingTimer, Dead, Downed, GarbageAttack, Health, PlayerScore, SurvivalTicks,
+    },
+    userinput::Userinput,
+};
+use constants::{AppState, GgrsConfig, KEY_BINDINGS_PATH, PHYSICS_DELTA, SIMULATION_FPS};
 use events::{
     physics_events::{
         CollisionEvent, ConveyorBrickTriggerEnterEvent, ConveyorBrickTriggerLeaveEvent,
@@ -20,44 +26,91 @@ use events::{
     player_events::{PlayerEnterDeadEvent, PlayerLeaveDeadEvent},
 };
 use resources::{
+    gamepads::{LocalGamepads, MenuFocus},
+    key_bindings::KeyBindings,
+    matchmaking::MatchmakingSetting,
+    network_diagnostics::NetworkDiagnostics,
     scoreboard::{ScoreTimer, Scoreboard},
+    session_config::LaunchArgs,
+    session_status::{MatchEnd, SimulationFrame},
+    touch_zones::{TouchZones, VirtualButtonsConfig},
     AppConfig, AppConfigAssets, CeilingAssets, ConveyorBrickAssets, FakeBrickAssets, InGameMode,
-    InGameSetting, NailsBrickAssets, NormalBrickAssets, PlayerAssets, SpringBrickAssets, UiAssets,
-    WallAssets,
+    InGameSetting, NailsBrickAssets, NormalBrickAssets, PlayerAssets, SpringBrickAssets, StageSize,
+    TeamHealth, UiAssets, WallAssets,
 };
 use systems::{
     animate_systems::animate_system,
+    camera_systems::{fit_camera_to_stage_system, resize_window_to_stage, restore_window_size},
     ceiling_systems::{celling_hurting_player_system, player_ceiling_hitbox_system},
     conveyor_brick_systems::player_on_conveyor_system,
+    coop_systems::{
+        init_team_health, player_down_system, player_revive_system, team_health_system,
+    },
     fake_brick_systems::{
         animate_fake_brick_system, fake_brick_flip_system, fake_brick_trigger_enter_system,
     },
+    gamepad_systems::{gamepad_connection_system, gamepad_menu_navigation_system},
+    garbage_systems::{garbage_attack_system, inject_garbage_system},
+    ghost_systems::{close_ghost_race, ghost_follow_system, spawn_ghost},
     in_game_once_systems::*,
     nails_brick_systems::player_nails_hitbox_system,
+    network_diagnostics_systems::{
+        advance_simulation_frame_system, toggle_network_diagnostics_system,
+        update_network_diagnostics_system,
+    },
     network_systems::{
-        close_matchbox_socket, network_input_system, start_matchbox_socket, wait_for_players,
+        close_ggrs_session, close_matchbox_socket, close_online_session, handle_ggrs_events_system,
+        init_ggrs_session_config, init_nickname, network_input_system,
+        receive_rematch_requests_system, refuse_late_peers_system, start_matchbox_socket,
+        start_rematch_system, wait_for_players,
     },
     normal_brick_systems::normal_brick_trigger_enter_system,
     physics_systems::{player_collision_system, velocity_system},
     player_systems::{
         animate_player_system, damaging_timer_system, enter_dead_system, enter_flying_system,
         enter_grounded_system, jumping_timer_system, leave_flying_system, leave_grounded_system,
-        player_controller_system, player_out_window_die_system,
+        match_end_system, player_controller_system, player_out_window_die_system,
+        survival_ticks_system,
     },
-    scoreboard_systems::{add_score, init_score},
+    replay_systems::{
+        close_replay_playback, finish_replay_recording_system, init_replay_playback,
+        record_replay_track_system, start_replay_recording,
+    },
+    scoreboard_systems::{add_score, add_score_fixed, init_score},
     spring_brick_systems::{animate_spring_brick_system, spring_brick_trigger_enter_system},
     ui::{
-        in_game_ui_systems::{update_health_text, update_score_text},
+        controls_ui_systems::{
+            capture_rebind_key_system, despawn_controls_ui_all, interact_with_rebind_key_button,
+            interact_with_reset_key_bindings_button, spawn_controls_ui_all, update_rebind_key_text,
+        },
+        in_game_ui_systems::{
+            update_health_text, update_network_diagnostics_text, update_network_status_text,
+            update_player_score_text, update_rematch_status_text, update_score_text,
+        },
+        join_room_ui_systems::{
+            despawn_join_room_ui_all, input_room_code_system,
+            interact_with_join_room_confirm_button, spawn_join_room_ui_all, update_room_code_text,
+        },
         main_menu_ui_systems::{
-            despawn_main_menu_ui_all, interact_with_online_play_button, interact_with_quit_button,
-            interact_with_single_play_button, spawn_main_menu_ui_all,
+            despawn_main_menu_ui_all, interact_with_controls_button,
+            interact_with_create_room_button, interact_with_join_room_button,
+            interact_with_local_players_button, interact_with_online_players_button,
+            interact_with_quick_match_button, interact_with_quit_button,
+            interact_with_race_ghost_button, interact_with_rules_button,
+            interact_with_settings_button, interact_with_single_play_button,
+            interact_with_watch_room_button, spawn_main_menu_ui_all,
         },
         matchmaking_ui_systems::{
             despawn_matchmaking_ui_all, interact_with_back_main_menu_button,
+            interact_with_matchmaking_ready_button, interact_with_matchmaking_retry_button,
             spawn_matchmaking_ui_all, update_matching_elapsed_time,
+            update_matchmaking_countdown_text, update_matchmaking_error_ui,
+            update_matchmaking_lobby_ui,
         },
+        settings_ui_systems::{despawn_settings_ui_all, spawn_settings_ui_all},
+        virtual_buttons_ui_systems::{spawn_virtual_buttons, virtual_buttons_system},
     },
-    userinput_system::userinput_system_2,
+    userinput_system::{init_touch_config, userinput_system_2},
     wall_systems::wall_reset_position_system,
 };
 
@@ -76,13 +129,37 @@ pub struct OnlineSet;
 pub struct OfflineSet;
 
 fn main() {
+    let launch_args: Vec<String> = std::env::args().skip(1).collect();
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(pair) = launch_args
+        .windows(2)
+        .find(|pair| pair[0] == "--verify-replay")
+    {
+        let matched = verify_replay(&pair[1]);
+        std::process::exit(if matched { 0 } else { 1 });
+    }
+
     let mut app = App::new();
 
     GGRSPlugin::<GgrsConfig>::new()
+        .with_update_frequency(SIMULATION_FPS)
         .with_input_system(network_input_system)
         .register_rollback_component::<Transform>()
+        .register_rollback_component::<Velocity>()
         .register_rollback_component::<Userinput>()
         .register_rollback_component::<Health>()
+        .register_rollback_component::<SurvivalTicks>()
+        .register_rollback_component::<GarbageAttack>()
+        .register_rollback_component::<Dead>()
+        .register_rollback_component::<Damaging>()
+        .register_rollback_component::<DamagingTimer>()
+        .register_rollback_component::<Downed>()
+        .register_rollback_resource::<TeamHealth>()
+        .register_rollback_resource::<SimulationFrame>()
+        .register_rollback_resource::<MatchEnd>()
+        .register_rollback_resource::<Scoreboard>()
+        .register_rollback_resource::<ScoreTimer>()
+        .register_rollback_component::<PlayerScore>()
         .build(&mut app);
 
     app.add_state::<AppState>()
@@ -123,31 +200,78 @@ fn main() {
             &["app_config.toml"],
         ))
         // .register_type::<DamagingTimer>()
-        .add_event::<CollisionEvent>()
-        .add_event::<TriggerEvent>()
-        .add_event::<NormalBrickTriggerEnterEvent>()
-        .add_event::<FakeBrickTriggerEnterEvent>()
-        .add_event::<SpringBrickTriggerEnterEvent>()
-        .add_event::<ConveyorBrickTriggerEnterEvent>()
-        .add_event::<ConveyorBrickTriggerLeaveEvent>()
-        .add_event::<PlayerEnterDeadEvent>()
-        .add_event::<PlayerLeaveDeadEvent>()
         .add_plugin(AudioPlugin)
         // .add_plugin(bevy_inspector_egui::quick::WorldInspectorPlugin::new())
-        .insert_resource(Scoreboard::default())
-        .insert_resource(ScoreTimer::default())
         .insert_resource(InGameSetting::new_offline_1p())
-        .insert_resource(FixedTime::new(Duration::from_secs_f64(PHYSICS_DELTA)))
-        .add_system(spawn_main_menu_ui_all.in_schedule(OnEnter(AppState::MainMenu)))
+        .insert_resource(MatchmakingSetting::default())
+        .insert_resource(NetworkDiagnostics::default())
+        .insert_resource(LaunchArgs(launch_args))
+        .insert_resource(KeyBindings::load(KEY_BINDINGS_PATH))
+        .init_resource::<TouchZones>()
+        .init_resource::<VirtualButtonsConfig>()
+        .init_resource::<LocalGamepads>()
+        .init_resource::<MenuFocus>()
+        .add_systems((gamepad_connection_system, gamepad_menu_navigation_system))
+        .add_systems(
+            (init_ggrs_session_config, init_nickname, init_touch_config)
+                .in_schedule(OnExit(AppState::AssetLoading)),
+        )
+        .add_systems(
+            (
+                spawn_main_menu_ui_all,
+                close_online_session,
+                close_replay_playback,
+                close_ghost_race,
+                init_replay_playback.after(close_replay_playback),
+            )
+                .in_schedule(OnEnter(AppState::MainMenu)),
+        )
         .add_system(despawn_main_menu_ui_all.in_schedule(OnExit(AppState::MainMenu)))
         .add_systems(
             (
                 interact_with_single_play_button,
-                interact_with_online_play_button,
+                interact_with_local_players_button,
+                interact_with_race_ghost_button,
+                interact_with_rules_button,
+                interact_with_online_players_button,
+                interact_with_quick_match_button,
+                interact_with_create_room_button,
+                interact_with_join_room_button,
+                interact_with_watch_room_button,
+                interact_with_settings_button,
+                interact_with_controls_button,
                 interact_with_quit_button,
             )
                 .in_set(OnUpdate(AppState::MainMenu)),
         )
+        .add_system(spawn_settings_ui_all.in_schedule(OnEnter(AppState::Settings)))
+        .add_system(despawn_settings_ui_all.in_schedule(OnExit(AppState::Settings)))
+        .add_system(interact_with_back_main_menu_button.in_set(OnUpdate(AppState::Settings)))
+        .add_system(spawn_controls_ui_all.in_schedule(OnEnter(AppState::Controls)))
+        .add_system(despawn_controls_ui_all.in_schedule(OnExit(AppState::Controls)))
+        .add_systems(
+            (
+                interact_with_rebind_key_button,
+                capture_rebind_key_system.after(interact_with_rebind_key_button),
+                interact_with_reset_key_bindings_button,
+                update_rebind_key_text
+                    .after(capture_rebind_key_system)
+                    .after(interact_with_reset_key_bindings_button),
+                interact_with_back_main_menu_button,
+            )
+                .in_set(OnUpdate(AppState::Controls)),
+        )
+        .add_system(spawn_join_room_ui_all.in_schedule(OnEnter(AppState::JoinRoom)))
+        .add_system(despawn_join_room_ui_all.in_schedule(OnExit(AppState::JoinRoom)))
+        .add_systems(
+            (
+                input_room_code_system,
+                update_room_code_text.after(input_room_code_system),
+                interact_with_join_room_confirm_button.after(input_room_code_system),
+                interact_with_back_main_menu_button,
+            )
+                .in_set(OnUpdate(AppState::JoinRoom)),
+        )
         .add_systems(
             (spawn_matchmaking_ui_all, start_matchbox_socket)
                 .in_schedule(OnEnter(AppState::Matchmaking)),
@@ -165,6 +289,11 @@ fn main() {
             (
                 wait_for_players,
                 update_matching_elapsed_time,
+                update_matchmaking_lobby_ui.after(wait_for_players),
+                update_matchmaking_countdown_text.after(wait_for_players),
+                update_matchmaking_error_ui.after(wait_for_players),
+                interact_with_matchmaking_ready_button.before(wait_for_players),
+                interact_with_matchmaking_retry_button,
                 interact_with_back_main_menu_button,
             )
                 .in_set(OnUpdate(AppState::Matchmaking)),
@@ -173,17 +302,37 @@ fn main() {
             (
                 play_background_sound,
                 spawn_camera,
-                // spawn_bricks,
-                spawn_bricks_2,
-                spawn_players,
-                spawn_walls,
-                spawn_ceiling,
-                init_score,
+                spawn_virtual_buttons,
+                spawn_ghost,
             )
                 .in_schedule(OnEnter(AppState::InGame)),
         )
         .add_systems(
-            (despawn_in_game_all, stop_background_sound).in_schedule(OnExit(AppState::InGame)),
+            (
+                despawn_in_game_all,
+                stop_background_sound,
+                restore_window_size,
+                close_ggrs_session,
+            )
+                .in_schedule(OnExit(AppState::InGame)),
+        )
+        .add_system(start_rematch_system.in_set(OnUpdate(AppState::Rematch)))
+        .add_systems(
+            (
+                handle_ggrs_events_system,
+                update_network_status_text.after(handle_ggrs_events_system),
+                receive_rematch_requests_system.after(handle_ggrs_events_system),
+                refuse_late_peers_system,
+                toggle_network_diagnostics_system,
+                update_network_diagnostics_system,
+                update_network_diagnostics_text
+                    .after(update_network_diagnostics_system)
+                    .after(toggle_network_diagnostics_system),
+            )
+                .distributive_run_if(|in_game_setting: Res<InGameSetting>| {
+                    in_game_setting.mode == InGameMode::Online
+                })
+                .in_set(OnUpdate(AppState::InGame)),
         )
         .add_systems(
             (
@@ -196,9 +345,11 @@ fn main() {
         )
         .add_systems(
             (
-                add_score,
-                update_score_text.after(add_score),
+                update_score_text,
                 update_health_text,
+                update_player_score_text,
+                virtual_buttons_system,
+                fit_camera_to_stage_system,
             )
                 .in_set(OnUpdate(AppState::InGame)),
         )
@@ -208,6 +359,7 @@ fn main() {
                 systems::ui::in_game_ui_systems::interact_play_again_button,
                 systems::ui::in_game_ui_systems::interact_back_to_main_menu_button,
                 systems::ui::in_game_ui_systems::spawn_in_game_result_menu_if_end,
+                update_rematch_status_text,
             )
                 .in_set(OnUpdate(AppState::InGame)),
         );
@@ -230,12 +382,207 @@ fn main() {
 
     // add_in_game_systems(&mut app, CoreSchedule::FixedUpdate, OfflineSet);
     // add_in_game_systems(&mut app, GGRSSchedule, OnlineSet);
-    add_in_game_systems(&mut app, CoreSchedule::FixedUpdate, 0);
+    add_simulation(&mut app);
+    app.add_system(
+        ghost_follow_system
+            .run_if(|state: Res<State<AppState>>| state.0 == AppState::InGame)
+            .in_schedule(CoreSchedule::FixedUpdate),
+    );
     add_in_game_systems(&mut app, GGRSSchedule, 1);
+    app.add_system(advance_simulation_frame_system.in_schedule(GGRSSchedule));
+    app.add_systems(
+        (
+            match_end_system
+                .after(advance_simulation_frame_system)
+                .after(survival_ticks_system),
+            add_score.after(match_end_system),
+        )
+            .distributive_run_if(|state: Res<State<AppState>>| state.0 == AppState::InGame)
+            .in_schedule(GGRSSchedule),
+    );
+
+    #[cfg(not(target_arch = "wasm32"))]
+    add_lan_systems(&mut app);
 
     app.run();
 }
 
+// everything that decides how an offline game plays out, shared with the headless replay check
+fn add_simulation(app: &mut App) {
+    app.add_event::<CollisionEvent>()
+        .add_event::<TriggerEvent>()
+        .add_event::<NormalBrickTriggerEnterEvent>()
+        .add_event::<FakeBrickTriggerEnterEvent>()
+        .add_event::<SpringBrickTriggerEnterEvent>()
+        .add_event::<ConveyorBrickTriggerEnterEvent>()
+        .add_event::<ConveyorBrickTriggerLeaveEvent>()
+        .add_event::<PlayerEnterDeadEvent>()
+        .add_event::<PlayerLeaveDeadEvent>()
+        .insert_resource(Scoreboard::default())
+        .insert_resource(ScoreTimer::default())
+        .init_resource::<StageSize>()
+        .init_resource::<TeamHealth>()
+        .insert_resource(FixedTime::new(Duration::from_secs_f64(PHYSICS_DELTA)))
+        .add_systems(
+            (
+                init_stage_size,
+                // spawn_bricks,
+                spawn_bricks_2.after(init_stage_size),
+                spawn_players.after(init_stage_size),
+                spawn_walls.after(init_stage_size),
+                spawn_ceiling.after(init_stage_size),
+                resize_window_to_stage.after(init_stage_size),
+                init_score,
+                init_team_health,
+                start_replay_recording.after(init_stage_size),
+            )
+                .in_schedule(OnEnter(AppState::InGame)),
+        )
+        .add_systems(
+            (
+                add_score_fixed.after(enter_dead_system),
+                record_replay_track_system.after(enter_dead_system),
+                finish_replay_recording_system
+                    .after(add_score_fixed)
+                    .after(record_replay_track_system)
+                    .after(userinput_system_2),
+            )
+                .distributive_run_if(|state: Res<State<AppState>>| state.0 == AppState::InGame)
+                .distributive_run_if(|in_game_setting: Res<InGameSetting>| {
+                    in_game_setting.mode == InGameMode::Offline
+                })
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
+    add_in_game_systems(app, CoreSchedule::FixedUpdate, 0);
+}
+
+// `--verify-replay <file>` simulates a replay without a window or assets, the exit code
+// tells whether it ended with the recorded score and depth
+#[cfg(not(target_arch = "wasm32"))]
+fn verify_replay(path: &str) -> bool {
+    use bevy::input::InputPlugin;
+    use resources::replay::{Replay, ReplayPlayback, ReplayRecorder};
+
+    let replay = match Replay::load(path) {
+        Ok(replay) => replay,
+        Err(err) => {
+            eprintln!("failed to load replay {}: {}", path, err);
+            return false;
+        }
+    };
+    let mut in_game_setting = InGameSetting::new_offline_1p();
+    in_game_setting.rules = replay.rules;
+    in_game_setting.set_offline_local(replay.num_players);
+    in_game_setting.seed = replay.seed;
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(AssetPlugin::default())
+        .add_plugin(InputPlugin)
+        .add_plugin(AudioPlugin)
+        .add_state::<AppState>()
+        .insert_resource(in_game_setting)
+        .insert_resource(ReplayPlayback::new(replay.clone()))
+        // default handles stand in for the assets, nothing is drawn
+        .init_resource::<PlayerAssets>()
+        .init_resource::<NormalBrickAssets>()
+        .init_resource::<FakeBrickAssets>()
+        .init_resource::<NailsBrickAssets>()
+        .init_resource::<SpringBrickAssets>()
+        .init_resource::<ConveyorBrickAssets>()
+        .init_resource::<WallAssets>()
+        .init_resource::<CeilingAssets>()
+        .init_resource::<UiAssets>()
+        .init_resource::<KeyBindings>()
+        .init_resource::<TouchZones>()
+        .init_resource::<VirtualButtonsConfig>()
+        .init_resource::<LocalGamepads>()
+        .insert_resource(NextState(Some(AppState::InGame)));
+    add_simulation(&mut app);
+
+    // enters the game, then every tick is run directly instead of waiting for the clock
+    app.update();
+    for _ in 0..=replay.ticks() {
+        app.world.run_schedule(CoreSchedule::FixedUpdate);
+        if app.world.resource::<ReplayRecorder>().finished {
+            break;
+        }
+    }
+
+    let recorder = app.world.resource::<ReplayRecorder>();
+    let result = &recorder.replay;
+    let matched = recorder.finished && replay.same_result(result);
+    println!(
+        "recorded: score {} depth {} ticks {}",
+        replay.score,
+        replay.depth,
+        replay.ticks()
+    );
+    println!(
+        "simulated: score {} depth {} ticks {}{}",
+        result.score,
+        result.depth,
+        result.ticks(),
+        if recorder.finished {
+            ""
+        } else {
+            " (still alive)"
+        }
+    );
+    println!(
+        "{}",
+        if matched {
+            "replay matched"
+        } else {
+            "replay diverged"
+        }
+    );
+    matched
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn add_lan_systems(app: &mut App) {
+    use systems::{
+        lan_systems::{
+            close_lan_lobby, discover_lan_hosts_system, lan_lobby_system, start_lan_lobby,
+        },
+        ui::{
+            lan_lobby_ui_systems::{
+                despawn_lan_lobby_ui_all, input_lan_address_system,
+                interact_with_lan_connect_button, interact_with_lan_host_button,
+                interact_with_lan_join_host_button, spawn_lan_lobby_ui_all,
+                update_lan_address_text, update_lan_host_list, update_lan_status_text,
+            },
+            main_menu_ui_systems::interact_with_lan_play_button,
+        },
+    };
+
+    app.add_system(interact_with_lan_play_button.in_set(OnUpdate(AppState::MainMenu)))
+        .add_systems(
+            (spawn_lan_lobby_ui_all, start_lan_lobby).in_schedule(OnEnter(AppState::LanLobby)),
+        )
+        .add_systems(
+            (despawn_lan_lobby_ui_all, close_lan_lobby).in_schedule(OnExit(AppState::LanLobby)),
+        )
+        .add_systems(
+            (
+                discover_lan_hosts_system,
+                update_lan_host_list.after(discover_lan_hosts_system),
+                input_lan_address_system,
+                update_lan_address_text.after(input_lan_address_system),
+                interact_with_lan_host_button,
+                interact_with_lan_join_host_button,
+                interact_with_lan_connect_button.after(input_lan_address_system),
+                lan_lobby_system,
+                update_lan_status_text
+                    .after(lan_lobby_system)
+                    .after(interact_with_lan_connect_button),
+                interact_with_back_main_menu_button,
+            )
+                .in_set(OnUpdate(AppState::LanLobby)),
+        );
+}
+
 fn add_in_game_systems(
     app: &mut App,
     schedule: impl ScheduleLabel + Clone,
@@ -244,7 +591,6 @@ fn add_in_game_systems(
 ) {
     let systems = vec![
         (
-            // userinput_system,
             userinput_system_2,
             velocity_system,
             fake_brick_trigger_enter_system.after(player_collision_system),
@@ -270,7 +616,6 @@ fn add_in_game_systems(
         (
             player_controller_system
                 .before(userinput_system_2)
-                // .after(userinput_system)
                 .before(player_collision_system)
                 .before(velocity_system),
             damaging_timer_system,
@@ -306,6 +651,39 @@ fn add_in_game_systems(
             player_out_window_die_system
                 .before(enter_dead_system)
                 .ambiguous_with_all(),
+            survival_ticks_system.after(enter_dead_system),
+            // co-op only, each of them checks the setting
+            team_health_system
+                .after(normal_brick_trigger_enter_system)
+                .after(spring_brick_trigger_enter_system)
+                .after(player_nails_hitbox_system)
+                .after(player_ceiling_hitbox_system)
+                .after(player_on_conveyor_system)
+                .before(enter_dead_system),
+            player_down_system
+                .after(team_health_system)
+                .after(enter_grounded_system)
+                .after(leave_grounded_system)
+                .after(enter_flying_system)
+                .after(leave_flying_system)
+                .before(player_out_window_die_system),
+            player_revive_system
+                .after(team_health_system)
+                .after(enter_grounded_system)
+                .after(player_down_system)
+                .before(enter_dead_system),
+        )
+            .distributive_run_if(|state: Res<State<AppState>>| state.0 == AppState::InGame),
+        (
+            // online versus only, each of them checks the setting
+            garbage_attack_system
+                .after(player_collision_system)
+                .after(player_nails_hitbox_system)
+                .after(player_ceiling_hitbox_system)
+                .after(player_on_conveyor_system)
+                .after(team_health_system)
+                .after(player_revive_system),
+            inject_garbage_system.after(garbage_attack_system),
         )
             .distributive_run_if(|state: Res<State<AppState>>| state.0 == AppState::InGame),
     ];
@@ -2,22 +2,62 @@ use bevy::{prelude::*, reflect::TypeUuid};
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::AudioSource;
 
+use crate::{
+    components::player::Health,
+    constants::{DEFAULT_STAGE_SEED, MAX_LOCAL_PLAYERS, MAX_ONLINE_PLAYERS, MIN_ONLINE_PLAYERS},
+};
+
+use self::{
+    session_config::GgrsSessionConfig,
+    touch_zones::{TouchZones, VirtualButtonsConfig},
+};
+
 pub mod floor_stage;
+pub mod gamepads;
+pub mod key_bindings;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lan_lobby;
+pub mod matchmaking;
+pub mod network_diagnostics;
+pub mod replay;
 pub mod scoreboard;
+pub mod session_config;
+pub mod session_status;
+pub mod touch_zones;
 
 #[derive(serde::Deserialize, TypeUuid)]
 #[uuid = "413be529-bfeb-41b3-9db0-4b8b380a2c46"]
 pub struct AppConfig {
-    pub signaling_server_addr: String
+    pub signaling_server_addr: String,
+    // seconds
+    #[serde(default = "default_signaling_connect_timeout")]
+    pub signaling_connect_timeout: f32,
+    // seconds, 0 waits forever
+    #[serde(default = "default_matchmaking_timeout")]
+    pub matchmaking_timeout: f32,
+    #[serde(default)]
+    pub ggrs: GgrsSessionConfig,
+    #[serde(default)]
+    pub touch: TouchZones,
+    #[serde(default)]
+    pub virtual_buttons: VirtualButtonsConfig,
+}
+
+fn default_signaling_connect_timeout() -> f32 {
+    10.0
 }
 
-#[derive(AssetCollection, Resource)]
+fn default_matchmaking_timeout() -> f32 {
+    120.0
+}
+
+#[derive(AssetCollection, Resource, Default)]
 pub struct AppConfigAssets {
     #[asset(path = "configs/main.app_config.toml")]
     pub main: Handle<AppConfig>,
 }
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct PlayerAssets {
     #[asset(texture_atlas(
         tile_size_x = 32.0,
@@ -34,7 +74,7 @@ pub struct PlayerAssets {
     pub die: Handle<AudioSource>,
 }
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct NormalBrickAssets {
     #[asset(texture_atlas(
         tile_size_x = 95.0,
@@ -51,7 +91,7 @@ pub struct NormalBrickAssets {
     pub hit: Handle<AudioSource>,
 }
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct NailsBrickAssets {
     #[asset(texture_atlas(
         tile_size_x = 96.0,
@@ -68,7 +108,7 @@ pub struct NailsBrickAssets {
     pub hit: Handle<AudioSource>,
 }
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct FakeBrickAssets {
     #[asset(texture_atlas(
         tile_size_x = 97.0,
@@ -85,7 +125,7 @@ pub struct FakeBrickAssets {
     pub hit: Handle<AudioSource>,
 }
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct SpringBrickAssets {
     #[asset(texture_atlas(
         tile_size_x = 97.0,
@@ -102,7 +142,7 @@ pub struct SpringBrickAssets {
     pub hit: Handle<AudioSource>,
 }
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct ConveyorBrickAssets {
     #[asset(texture_atlas(
         tile_size_x = 96.0,
@@ -130,7 +170,7 @@ pub struct ConveyorBrickAssets {
     pub hit: Handle<AudioSource>,
 }
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct WallAssets {
     #[asset(texture_atlas(
         tile_size_x = 18.0,
@@ -144,7 +184,7 @@ pub struct WallAssets {
     pub sprite_sheet: Handle<TextureAtlas>,
 }
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct UiAssets {
     #[asset(path = "fonts/FiraMono-Medium.ttf")]
     pub medium_font: Handle<Font>,
@@ -153,7 +193,7 @@ pub struct UiAssets {
     pub bold_font: Handle<Font>,
 }
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct CeilingAssets {
     #[asset(texture_atlas(
         tile_size_x = 800.0,
@@ -167,6 +207,44 @@ pub struct CeilingAssets {
     pub sprite_sheet: Handle<TextureAtlas>,
 }
 
+// the playfield the stage was built for, fixed for the whole game so a replay
+// simulates the same way whatever the window size or without a window at all
+#[derive(Resource, Default, Clone, Copy)]
+pub struct StageSize {
+    // of a single shaft
+    pub width: f32,
+    pub height: f32,
+    // side by side, one per player in a race
+    pub num_shafts: usize,
+}
+
+impl StageSize {
+    pub fn total_width(&self) -> f32 {
+        self.width * self.num_shafts.max(1) as f32
+    }
+
+    // the shafts are centered around x = 0
+    pub fn shaft_x(&self, shaft: usize) -> f32 {
+        let center = (self.num_shafts.max(1) - 1) as f32 / 2.0;
+        (shaft as f32 - center) * self.width
+    }
+}
+
+// the health every co-op player shares, see `team_health_system`
+#[derive(Resource, Reflect, Clone, Copy)]
+#[reflect(Resource)]
+pub struct TeamHealth {
+    pub value: i32,
+}
+
+impl Default for TeamHealth {
+    fn default() -> Self {
+        Self {
+            value: Health::new_player_health().value,
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct LocalPlayerHandle(pub usize);
 
@@ -177,10 +255,37 @@ pub enum InGameMode {
     Online,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum GameRules {
+    #[default]
+    Versus,
+    // one health pool for everyone, see `coop_systems`
+    Coop,
+    // versus, every player in their own shaft of the same stage
+    Race,
+}
+
+impl GameRules {
+    pub const ALL: [GameRules; 3] = [GameRules::Versus, GameRules::Coop, GameRules::Race];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameRules::Versus => "Versus",
+            GameRules::Coop => "Co-op",
+            GameRules::Race => "Race",
+        }
+    }
+}
+
 #[derive(Resource, Default, Clone, Copy)]
 pub struct InGameSetting {
     pub mode: InGameMode,
     pub num_players: usize,
+    pub is_spectator: bool,
+    pub seed: u64,
+    // picked in the main menu and kept when the mode changes, only means something
+    // with more than one player
+    pub rules: GameRules,
 }
 
 impl InGameSetting {
@@ -188,21 +293,93 @@ impl InGameSetting {
         Self {
             mode: InGameMode::Offline,
             num_players: 1,
+            is_spectator: false,
+            seed: DEFAULT_STAGE_SEED,
+            rules: GameRules::Versus,
         }
     }
 
-    pub fn new_online_2p() -> Self {
+    pub fn new_online(num_players: usize) -> Self {
         Self {
             mode: InGameMode::Online,
-            num_players: 2,
+            num_players: num_players.clamp(MIN_ONLINE_PLAYERS, MAX_ONLINE_PLAYERS),
+            is_spectator: false,
+            seed: DEFAULT_STAGE_SEED,
+            rules: GameRules::Versus,
+        }
+    }
+
+    pub fn new_online_spectator(num_players: usize) -> Self {
+        Self {
+            is_spectator: true,
+            ..Self::new_online(num_players)
         }
     }
 
-    pub fn set_online_2p(&mut self) {
-        self.clone_from(&Self::new_online_2p());
+    pub fn set_online(&mut self, num_players: usize) {
+        self.clone_from(&Self {
+            rules: self.rules,
+            ..Self::new_online(num_players)
+        });
+    }
+
+    pub fn set_online_spectator(&mut self, num_players: usize) {
+        self.clone_from(&Self {
+            rules: self.rules,
+            ..Self::new_online_spectator(num_players)
+        });
     }
 
     pub fn set_offline_1p(&mut self) {
-        self.clone_from(&&Self::new_offline_1p());
+        self.clone_from(&Self {
+            rules: self.rules,
+            ..Self::new_offline_1p()
+        });
+    }
+
+    // everyone plays on this device, e.g. split keyboard
+    pub fn set_offline_local(&mut self, num_players: usize) {
+        self.clone_from(&Self {
+            num_players: num_players.clamp(1, MAX_LOCAL_PLAYERS),
+            rules: self.rules,
+            ..Self::new_offline_1p()
+        });
+    }
+
+    pub fn is_coop(&self) -> bool {
+        self.rules == GameRules::Coop && self.num_players > 1
+    }
+
+    pub fn is_race(&self) -> bool {
+        self.rules == GameRules::Race && self.num_players > 1
+    }
+
+    // online versus players in one shaft send each other garbage
+    pub fn has_garbage_attack(&self) -> bool {
+        self.mode == InGameMode::Online && self.rules == GameRules::Versus
+    }
+
+    pub fn num_shafts(&self) -> usize {
+        if self.is_race() {
+            self.num_players
+        } else {
+            1
+        }
+    }
+
+    pub fn player_shaft(&self, handle: usize) -> usize {
+        if self.is_race() {
+            handle
+        } else {
+            0
+        }
+    }
+
+    pub fn num_local_players(&self) -> usize {
+        match self.mode {
+            InGameMode::Offline => self.num_players,
+            InGameMode::Online if self.is_spectator => 0,
+            InGameMode::Online => 1,
+        }
     }
 }
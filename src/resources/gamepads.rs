@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+use crate::constants::MAX_LOCAL_PLAYERS;
+
+// controllers in connection order, `gamepad` tells which local player drives which
+#[derive(Resource)]
+pub struct LocalGamepads {
+    pub players: Vec<Option<Gamepad>>,
+}
+
+impl Default for LocalGamepads {
+    fn default() -> Self {
+        Self {
+            players: vec![None; MAX_LOCAL_PLAYERS],
+        }
+    }
+}
+
+impl LocalGamepads {
+    // the keyboard is shared, so controllers are handed out from the last local player,
+    // with one controller in local 2P the first player keeps the keyboard
+    pub fn gamepad(&self, local_player: usize, num_local_players: usize) -> Option<Gamepad> {
+        let slot = num_local_players.checked_sub(local_player + 1)?;
+        self.players.get(slot).copied().flatten()
+    }
+
+    // a reconnected controller takes the first free slot, usually the one it just left
+    pub fn assign(&mut self, gamepad: Gamepad) -> Option<usize> {
+        if let Some(i) = self.players.iter().position(|x| *x == Some(gamepad)) {
+            return Some(i);
+        }
+        let i = self.players.iter().position(|x| x.is_none())?;
+        self.players[i] = Some(gamepad);
+        Some(i)
+    }
+
+    pub fn unassign(&mut self, gamepad: Gamepad) -> Option<usize> {
+        let i = self.players.iter().position(|x| *x == Some(gamepad))?;
+        self.players[i] = None;
+        Some(i)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct MenuFocus {
+    pub focused: Option<Entity>,
+    // the button "clicked" with a controller last frame, released on the next one
+    pub clicked: Option<Entity>,
+}
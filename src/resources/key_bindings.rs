@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::MAX_LOCAL_PLAYERS;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum InputAction {
+    MoveLeft,
+    MoveRight,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 2] = [InputAction::MoveLeft, InputAction::MoveRight];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputAction::MoveLeft => "Move Left",
+            InputAction::MoveRight => "Move Right",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerKeyBindings {
+    pub move_left: Vec<KeyCode>,
+    pub move_right: Vec<KeyCode>,
+}
+
+impl PlayerKeyBindings {
+    pub fn keys(&self, action: InputAction) -> &[KeyCode] {
+        match action {
+            InputAction::MoveLeft => &self.move_left,
+            InputAction::MoveRight => &self.move_right,
+        }
+    }
+
+    pub fn keys_mut(&mut self, action: InputAction) -> &mut Vec<KeyCode> {
+        match action {
+            InputAction::MoveLeft => &mut self.move_left,
+            InputAction::MoveRight => &mut self.move_right,
+        }
+    }
+}
+
+// one binding set per local player, the first one is also used online
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct KeyBindings {
+    pub players: Vec<PlayerKeyBindings>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            players: vec![
+                PlayerKeyBindings {
+                    move_left: vec![KeyCode::A, KeyCode::Left],
+                    move_right: vec![KeyCode::D, KeyCode::Right],
+                },
+                PlayerKeyBindings {
+                    move_left: vec![KeyCode::Left],
+                    move_right: vec![KeyCode::Right],
+                },
+            ],
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn player(&self, local_player: usize) -> Option<&PlayerKeyBindings> {
+        self.players.get(local_player)
+    }
+
+    // files from older versions may have fewer players
+    fn fill_missing_players(&mut self) {
+        let defaults = KeyBindings::default();
+        self.players.truncate(MAX_LOCAL_PLAYERS);
+        for i in self.players.len()..MAX_LOCAL_PLAYERS {
+            self.players.push(defaults.players[i].clone());
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> Self {
+        let mut bindings = match std::fs::read_to_string(path) {
+            Ok(text) => match toml::from_str::<KeyBindings>(&text) {
+                Ok(bindings) => bindings,
+                Err(err) => {
+                    warn!("invalid key bindings in {}, using defaults: {}", path, err);
+                    KeyBindings::default()
+                }
+            },
+            Err(_) => KeyBindings::default(),
+        };
+        bindings.fill_missing_players();
+        bindings
+    }
+
+    // there is no file system in the browser, bindings last until the page is closed
+    #[cfg(target_arch = "wasm32")]
+    pub fn load(_path: &str) -> Self {
+        KeyBindings::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &str) {
+        let result = toml::to_string_pretty(self)
+            .map_err(|err| err.to_string())
+            .and_then(|text| std::fs::write(path, text).map_err(|err| err.to_string()));
+        match result {
+            Ok(_) => info!("saved key bindings to {}", path),
+            Err(err) => error!("failed to save key bindings to {}: {}", path, err),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self, _path: &str) {}
+}
+
+// the binding waiting for a key press on the controls screen
+#[derive(Resource, Default)]
+pub struct KeyRebinding {
+    pub target: Option<(usize, InputAction)>,
+}
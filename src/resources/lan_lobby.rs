@@ -0,0 +1,62 @@
+use std::net::SocketAddr;
+
+use bevy::prelude::*;
+
+use crate::{resources::GameRules, utils::network_utils::LanSocket};
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum LanLobbyMode {
+    #[default]
+    Idle,
+    Hosting {
+        joiners: Vec<SocketAddr>,
+    },
+    // everyone joined, waiting for the joiners to acknowledge the start
+    Starting {
+        joiners: Vec<SocketAddr>,
+        acked: Vec<SocketAddr>,
+        started_at: f32,
+    },
+    Joining {
+        host: SocketAddr,
+    },
+}
+
+#[derive(Resource, Default)]
+pub struct LanLobby {
+    pub mode: LanLobbyMode,
+    pub last_sent_at: f32,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LanHost {
+    pub addr: SocketAddr,
+    pub num_players: usize,
+    pub num_joined: usize,
+    pub rules: GameRules,
+    pub last_seen_at: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct LanDiscovery {
+    pub socket: Option<LanSocket>,
+    pub hosts: Vec<LanHost>,
+}
+
+#[derive(Resource, Default)]
+pub struct LanAddressInput {
+    pub value: String,
+    pub invalid: bool,
+}
+
+impl LanAddressInput {
+    // the port can be left out when the host uses the default one
+    pub fn parse(&self, default_port: u16) -> Option<SocketAddr> {
+        let value = self.value.trim();
+        value
+            .parse()
+            .ok()
+            .or_else(|| format!("{}:{}", value, default_port).parse().ok())
+    }
+}
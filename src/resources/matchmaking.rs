@@ -0,0 +1,237 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_matchbox::prelude::PeerId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{MAX_ONLINE_PLAYERS, MIN_ONLINE_PLAYERS, NICKNAME_MAX_LEN},
+    resources::GameRules,
+};
+
+// no 0/O or 1/I so codes can be read out loud
+const ROOM_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const ROOM_CODE_SUFFIX_LEN: usize = 4;
+
+pub const ROOM_CODE_LEN: usize = ROOM_CODE_SUFFIX_LEN + 1;
+
+// leading char of a room for 2, 3 and 4 players
+fn room_code_prefixes(rules: GameRules) -> &'static [u8] {
+    match rules {
+        GameRules::Versus => b"234",
+        GameRules::Coop => b"789",
+        GameRules::Race => b"RST",
+    }
+}
+
+// first char is the rules and the number of players, e.g. "3KQXA" is a 3 players versus
+// room, "8KQXA" a 3 players co-op room and "SKQXA" a 3 players race
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RoomCode(String);
+
+impl RoomCode {
+    pub fn generate(num_players: usize, rules: GameRules, rng: &fastrand::Rng) -> Self {
+        let num_players = num_players.clamp(MIN_ONLINE_PLAYERS, MAX_ONLINE_PLAYERS);
+        let mut code = String::new();
+        code.push(room_code_prefixes(rules)[num_players - MIN_ONLINE_PLAYERS] as char);
+        for _ in 0..ROOM_CODE_SUFFIX_LEN {
+            let i = rng.usize(0..ROOM_CODE_ALPHABET.len());
+            code.push(ROOM_CODE_ALPHABET[i] as char);
+        }
+        Self(code)
+    }
+
+    pub fn parse(input: &str) -> Option<Self> {
+        let code = input.trim().to_ascii_uppercase();
+        if code.len() != ROOM_CODE_LEN {
+            return None;
+        }
+        Self::decode_prefix(code.as_bytes()[0])?;
+        if !code[1..]
+            .chars()
+            .all(|c| ROOM_CODE_ALPHABET.contains(&(c as u8)))
+        {
+            return None;
+        }
+        Some(Self(code))
+    }
+
+    fn decode_prefix(prefix: u8) -> Option<(GameRules, usize)> {
+        GameRules::ALL.into_iter().find_map(|rules| {
+            room_code_prefixes(rules)
+                .iter()
+                .position(|x| *x == prefix)
+                .map(|i| (rules, i + MIN_ONLINE_PLAYERS))
+        })
+    }
+
+    pub fn num_players(&self) -> usize {
+        Self::decode_prefix(self.0.as_bytes()[0]).map_or(MIN_ONLINE_PLAYERS, |(_, x)| x)
+    }
+
+    pub fn rules(&self) -> GameRules {
+        Self::decode_prefix(self.0.as_bytes()[0]).map_or_else(GameRules::default, |(x, _)| x)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum MatchmakingRoom {
+    // players are only matched with others who picked the same rules
+    QuickMatch { rules: GameRules },
+    Private(RoomCode),
+}
+
+impl MatchmakingRoom {
+    pub fn room_name(&self) -> String {
+        match self {
+            MatchmakingRoom::QuickMatch { rules } => match rules {
+                GameRules::Versus => "quick_match".to_string(),
+                GameRules::Coop => "quick_match_coop".to_string(),
+                GameRules::Race => "quick_match_race".to_string(),
+            },
+            MatchmakingRoom::Private(code) => format!("room_{}", code.as_str()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum MatchmakingRole {
+    #[default]
+    Player,
+    Spectator,
+}
+
+#[derive(Resource, Clone)]
+pub struct MatchmakingSetting {
+    pub num_players: usize,
+    pub room: MatchmakingRoom,
+    pub role: MatchmakingRole,
+}
+
+impl Default for MatchmakingSetting {
+    fn default() -> Self {
+        Self {
+            num_players: MIN_ONLINE_PLAYERS,
+            room: MatchmakingRoom::QuickMatch {
+                rules: GameRules::Versus,
+            },
+            role: MatchmakingRole::Player,
+        }
+    }
+}
+
+impl MatchmakingSetting {
+    pub fn room_url(&self, base_url: &str) -> String {
+        match self.room {
+            MatchmakingRoom::QuickMatch { .. } => format!(
+                "{}/{}?next={}",
+                base_url,
+                self.room.room_name(),
+                self.num_players
+            ),
+            // private rooms are a full mesh so spectators can join the same room
+            MatchmakingRoom::Private(_) => format!("{}/{}", base_url, self.room.room_name()),
+        }
+    }
+}
+
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct PeerRoles(pub HashMap<PeerId, MatchmakingRole>);
+
+impl PeerRoles {
+    pub fn peers_with_role(&self, role: MatchmakingRole) -> Vec<PeerId> {
+        let mut peers: Vec<PeerId> = self
+            .iter()
+            .filter(|(_, x)| **x == role)
+            .map(|(peer, _)| *peer)
+            .collect();
+        peers.sort();
+        peers
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct Nickname(pub String);
+
+impl Nickname {
+    pub fn new(value: &str) -> Option<Self> {
+        let value: String = value.trim().chars().take(NICKNAME_MAX_LEN).collect();
+        (!value.is_empty()).then_some(Self(value))
+    }
+
+    pub fn generate(rng: &fastrand::Rng) -> Self {
+        Self(format!("Player {}", rng.u16(100..1000)))
+    }
+}
+
+// the lobby between the room filling up and the ggrs session starting
+#[derive(Resource, Default)]
+pub struct MatchLobby {
+    // sorted like the player handles, empty until enough players have joined
+    pub players: Vec<PeerId>,
+    pub local_id: Option<PeerId>,
+    pub nicknames: HashMap<PeerId, String>,
+    pub ready_peers: HashSet<PeerId>,
+    pub local_ready: bool,
+    pub sent_ready: bool,
+    pub countdown_started_at: Option<f32>,
+    // the players the host started with, spectators wait for it
+    pub host_start: Option<Vec<PeerId>>,
+}
+
+impl MatchLobby {
+    pub fn is_ready(&self, peer: PeerId) -> bool {
+        match self.local_id {
+            Some(local_id) if local_id == peer => self.local_ready,
+            _ => self.ready_peers.contains(&peer),
+        }
+    }
+
+    pub fn all_ready(&self) -> bool {
+        !self.players.is_empty() && self.players.iter().all(|x| self.is_ready(*x))
+    }
+
+    pub fn remove_peer(&mut self, peer: PeerId) {
+        self.nicknames.remove(&peer);
+        self.ready_peers.remove(&peer);
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct MatchmakingStatus {
+    pub room_url: String,
+    pub started_at: f32,
+    pub connect_timeout: f32,
+    pub match_timeout: f32,
+    pub connected: bool,
+    pub error: Option<String>,
+    pub socket_closed: SocketClosed,
+}
+
+// set when the socket's message loop ends, it only does when the signaling server
+// connection failed or closed
+#[derive(Clone, Default)]
+pub struct SocketClosed(Arc<Mutex<Option<String>>>);
+
+impl SocketClosed {
+    pub fn set(&self, cause: String) {
+        *self.0.lock().unwrap() = Some(cause);
+    }
+
+    pub fn cause(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct RoomCodeInput {
+    pub value: String,
+    pub invalid: bool,
+}
@@ -0,0 +1,11 @@
+use bevy::prelude::*;
+
+#[derive(Resource, Default)]
+pub struct NetworkDiagnostics {
+    pub visible: bool,
+    // `SessionStatus::rollbacks` when the current second started
+    pub window_rollbacks: u32,
+    pub rollbacks_per_sec: u32,
+    pub window_started_at: f32,
+    pub prediction_depth: i32,
+}
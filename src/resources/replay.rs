@@ -0,0 +1,363 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{REPLAY_MAGIC, REPLAY_VERSION},
+    resources::GameRules,
+    utils::input_utils::PlayerInput,
+};
+
+// consecutive ticks where every player sent the same input
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+struct InputRun {
+    ticks: u32,
+    inputs: Vec<PlayerInput>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+struct ReplayHeader {
+    magic: [u8; 4],
+    version: u16,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReplayBody {
+    seed: u64,
+    num_players: u8,
+    stage_width: u16,
+    stage_height: u16,
+    score: i32,
+    depth: u32,
+    runs: Vec<InputRun>,
+}
+
+// an offline run, the stage and every input needed to simulate it again
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub num_players: usize,
+    pub stage_width: u16,
+    pub stage_height: u16,
+    // `num_players` inputs per tick, in player handle order
+    pub inputs: Vec<PlayerInput>,
+    pub score: i32,
+    // floors passed before the last player died
+    pub depth: u32,
+    // where the first player was after every tick, what a ghost follows
+    pub track: Vec<Vec2>,
+    pub rules: GameRules,
+}
+
+impl Replay {
+    pub fn ticks(&self) -> usize {
+        self.inputs.len() / self.num_players.max(1)
+    }
+
+    pub fn tick_inputs(&self, tick: usize) -> Option<&[PlayerInput]> {
+        let n = self.num_players;
+        self.inputs.get(tick * n..(tick + 1) * n)
+    }
+
+    pub fn same_result(&self, other: &Replay) -> bool {
+        self.score == other.score && self.depth == other.depth
+    }
+
+    pub fn is_better_than(&self, other: &Replay) -> bool {
+        (self.score, self.depth) > (other.score, other.depth)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut runs: Vec<InputRun> = vec![];
+        for inputs in self.inputs.chunks_exact(self.num_players.max(1)) {
+            match runs.last_mut() {
+                Some(run) if run.inputs == inputs => run.ticks += 1,
+                _ => runs.push(InputRun {
+                    ticks: 1,
+                    inputs: inputs.to_vec(),
+                }),
+            }
+        }
+        let header = ReplayHeader {
+            magic: REPLAY_MAGIC,
+            version: REPLAY_VERSION,
+        };
+        let body = ReplayBody {
+            seed: self.seed,
+            num_players: self.num_players as u8,
+            stage_width: self.stage_width,
+            stage_height: self.stage_height,
+            score: self.score,
+            depth: self.depth,
+            runs,
+        };
+
+        let track: Vec<[i16; 2]> = self
+            .track
+            .iter()
+            .map(|position| [position.x.round() as i16, position.y.round() as i16])
+            .collect();
+
+        let mut bytes = bincode::serialize(&header).map_err(|err| err.to_string())?;
+        bincode::serialize_into(&mut bytes, &body).map_err(|err| err.to_string())?;
+        bincode::serialize_into(&mut bytes, &track).map_err(|err| err.to_string())?;
+        bincode::serialize_into(&mut bytes, &self.rules).map_err(|err| err.to_string())?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, String> {
+        let header: ReplayHeader =
+            bincode::deserialize_from(&mut bytes).map_err(|err| err.to_string())?;
+        if header.magic != REPLAY_MAGIC {
+            return Err("not a replay file".to_string());
+        }
+        // the body layout is only known for versions we wrote
+        if header.version == 0 || header.version > REPLAY_VERSION {
+            return Err(format!("unsupported replay version {}", header.version));
+        }
+        let body: ReplayBody =
+            bincode::deserialize_from(&mut bytes).map_err(|err| err.to_string())?;
+        // version 1 files have no track and can't be raced as a ghost
+        let track: Vec<[i16; 2]> = if header.version >= 2 {
+            bincode::deserialize_from(&mut bytes).map_err(|err| err.to_string())?
+        } else {
+            vec![]
+        };
+        // version 3 files only knew co-op or versus, older ones are all versus
+        let rules = match header.version {
+            4.. => bincode::deserialize_from(&mut bytes).map_err(|err| err.to_string())?,
+            3 => {
+                let coop: bool =
+                    bincode::deserialize_from(&mut bytes).map_err(|err| err.to_string())?;
+                if coop {
+                    GameRules::Coop
+                } else {
+                    GameRules::Versus
+                }
+            }
+            _ => GameRules::Versus,
+        };
+
+        let num_players = body.num_players as usize;
+        let mut inputs = vec![];
+        for run in body.runs {
+            if run.inputs.len() != num_players {
+                return Err("input run doesn't match the number of players".to_string());
+            }
+            for _ in 0..run.ticks {
+                inputs.extend_from_slice(&run.inputs);
+            }
+        }
+        Ok(Self {
+            seed: body.seed,
+            num_players,
+            stage_width: body.stage_width,
+            stage_height: body.stage_height,
+            inputs,
+            score: body.score,
+            depth: body.depth,
+            track: track
+                .into_iter()
+                .map(|[x, y]| Vec2::new(x as f32, y as f32))
+                .collect(),
+            rules,
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|err| err.to_string())?;
+        Self::from_bytes(&bytes)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let bytes = self.to_bytes()?;
+        std::fs::write(path, bytes).map_err(|err| err.to_string())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load(_path: &str) -> Result<Self, String> {
+        Err("no file system in the browser".to_string())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self, _path: &str) -> Result<(), String> {
+        Err("no file system in the browser".to_string())
+    }
+}
+
+// collects the inputs of the offline game being played, or replayed
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    pub replay: Replay,
+    pub finished: bool,
+}
+
+impl ReplayRecorder {
+    pub fn push(&mut self, inputs: &[PlayerInput]) {
+        if !self.finished {
+            self.replay.inputs.extend_from_slice(inputs);
+        }
+    }
+
+    pub fn push_position(&mut self, position: Vec2) {
+        if !self.finished {
+            self.replay.track.push(position);
+        }
+    }
+}
+
+// feeds a recorded run to the players instead of the local input
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    pub tick: usize,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, tick: 0 }
+    }
+
+    // players stand still once the recording runs out
+    pub fn next_inputs(&mut self) -> Vec<PlayerInput> {
+        let inputs = match self.replay.tick_inputs(self.tick) {
+            Some(inputs) => inputs.to_vec(),
+            None => vec![PlayerInput::default(); self.replay.num_players],
+        };
+        self.tick += 1;
+        inputs
+    }
+}
+
+// a recorded run shown next to the live player, see `ghost_systems`
+#[derive(Resource)]
+pub struct GhostRace {
+    pub replay: Replay,
+    pub tick: usize,
+}
+
+impl GhostRace {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, tick: 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_replay() -> Replay {
+        let left = PlayerInput::new(-1.0, 0);
+        let right = PlayerInput::new(1.0, 0);
+        Replay {
+            seed: 42,
+            num_players: 2,
+            stage_width: 540,
+            stage_height: 960,
+            inputs: vec![left, right, left, right, right, right, left, left],
+            score: 1234,
+            depth: 56,
+            track: vec![Vec2::new(0.0, 200.0), Vec2::new(-3.0, 198.0)],
+            rules: GameRules::Race,
+        }
+    }
+
+    // the layout every version wrote, `extra` is what comes after the body
+    fn encode(version: u16, extra: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+        let replay = sample_replay();
+        let header = ReplayHeader {
+            magic: REPLAY_MAGIC,
+            version,
+        };
+        let body = ReplayBody {
+            seed: replay.seed,
+            num_players: replay.num_players as u8,
+            stage_width: replay.stage_width,
+            stage_height: replay.stage_height,
+            score: replay.score,
+            depth: replay.depth,
+            runs: replay
+                .inputs
+                .chunks_exact(replay.num_players)
+                .map(|inputs| InputRun {
+                    ticks: 1,
+                    inputs: inputs.to_vec(),
+                })
+                .collect(),
+        };
+        let mut bytes = bincode::serialize(&header).unwrap();
+        bincode::serialize_into(&mut bytes, &body).unwrap();
+        extra(&mut bytes);
+        bytes
+    }
+
+    fn encoded_track() -> Vec<[i16; 2]> {
+        vec![[0, 200], [-3, 198]]
+    }
+
+    #[test]
+    fn round_trip() {
+        let replay = sample_replay();
+        let decoded = Replay::from_bytes(&replay.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, replay);
+        assert_eq!(decoded.ticks(), 4);
+    }
+
+    #[test]
+    fn decodes_version_1() {
+        let replay = Replay::from_bytes(&encode(1, |_| {})).unwrap();
+        assert_eq!(replay.inputs, sample_replay().inputs);
+        assert!(replay.track.is_empty());
+        assert_eq!(replay.rules, GameRules::Versus);
+    }
+
+    #[test]
+    fn decodes_version_2() {
+        let bytes = encode(2, |bytes| {
+            bincode::serialize_into(bytes, &encoded_track()).unwrap();
+        });
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(replay.track, sample_replay().track);
+        assert_eq!(replay.rules, GameRules::Versus);
+    }
+
+    #[test]
+    fn decodes_version_3() {
+        let bytes = encode(3, |bytes| {
+            bincode::serialize_into(&mut *bytes, &encoded_track()).unwrap();
+            bincode::serialize_into(bytes, &true).unwrap();
+        });
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(replay.track, sample_replay().track);
+        assert_eq!(replay.rules, GameRules::Coop);
+    }
+
+    #[test]
+    fn decodes_version_4() {
+        let bytes = encode(4, |bytes| {
+            bincode::serialize_into(&mut *bytes, &encoded_track()).unwrap();
+            bincode::serialize_into(bytes, &GameRules::Race).unwrap();
+        });
+        assert_eq!(Replay::from_bytes(&bytes).unwrap(), sample_replay());
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut bytes = sample_replay().to_bytes().unwrap();
+        bytes[0] = b'X';
+        assert!(Replay::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let bytes = encode(REPLAY_VERSION + 1, |bytes| {
+            bincode::serialize_into(&mut *bytes, &encoded_track()).unwrap();
+            bincode::serialize_into(bytes, &GameRules::Race).unwrap();
+        });
+        assert_eq!(
+            Replay::from_bytes(&bytes),
+            Err(format!("unsupported replay version {}", REPLAY_VERSION + 1))
+        );
+    }
+}
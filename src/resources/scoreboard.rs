@@ -1,11 +1,13 @@
 use bevy::prelude::*;
 
-#[derive(Resource, Default)]
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
 pub struct Scoreboard {
     pub score: i32,
 }
 
-#[derive(Resource, Deref, DerefMut)]
+#[derive(Resource, Reflect, Deref, DerefMut)]
+#[reflect(Resource)]
 pub struct ScoreTimer(Timer);
 
 impl Default for ScoreTimer {
@@ -0,0 +1,174 @@
+use std::{fmt::Debug, ops::RangeInclusive, str::FromStr, time::Duration};
+
+use bevy::prelude::*;
+use bevy_ggrs::ggrs::DesyncDetection;
+use serde::Deserialize;
+
+use crate::utils::network_simulator::NetworkSimulatorConfig;
+
+// ggrs keeps 128 frames of input, delay and prediction have to fit in it with room to spare
+const MAX_INPUT_DELAY: usize = 10;
+const MAX_PREDICTION: usize = 32;
+const DISCONNECT_TIMEOUT_MS: RangeInclusive<u64> = 500..=30_000;
+const MAX_SIMULATED_DELAY_MS: u64 = 5_000;
+
+// the `[ggrs]` table of the app config, every field falls back to the ggrs defaults
+#[derive(Resource, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct GgrsSessionConfig {
+    pub input_delay: usize,
+    pub max_prediction: usize,
+    // in frames, 0 turns desync detection off
+    pub desync_detection_interval: u32,
+    pub disconnect_timeout_ms: u64,
+    pub disconnect_notify_start_ms: u64,
+    // `[ggrs.network_simulator]`, for reproducing bad connections locally
+    pub network_simulator: NetworkSimulatorConfig,
+}
+
+impl Default for GgrsSessionConfig {
+    fn default() -> Self {
+        Self {
+            input_delay: 2,
+            max_prediction: 8,
+            desync_detection_interval: 0,
+            disconnect_timeout_ms: 2000,
+            disconnect_notify_start_ms: 500,
+            network_simulator: NetworkSimulatorConfig::default(),
+        }
+    }
+}
+
+impl GgrsSessionConfig {
+    pub fn desync_detection(&self) -> DesyncDetection {
+        match self.desync_detection_interval {
+            0 => DesyncDetection::Off,
+            interval => DesyncDetection::On { interval },
+        }
+    }
+
+    pub fn disconnect_timeout(&self) -> Duration {
+        Duration::from_millis(self.disconnect_timeout_ms)
+    }
+
+    pub fn disconnect_notify_start(&self) -> Duration {
+        Duration::from_millis(self.disconnect_notify_start_ms)
+    }
+
+    // values the session can't run with are logged and replaced by the defaults
+    pub fn validated(mut self) -> Self {
+        let default = Self::default();
+        reject_out_of_range(
+            "input_delay",
+            &mut self.input_delay,
+            0..=MAX_INPUT_DELAY,
+            default.input_delay,
+        );
+        reject_out_of_range(
+            "max_prediction",
+            &mut self.max_prediction,
+            1..=MAX_PREDICTION,
+            default.max_prediction,
+        );
+        reject_out_of_range(
+            "disconnect_timeout_ms",
+            &mut self.disconnect_timeout_ms,
+            DISCONNECT_TIMEOUT_MS,
+            default.disconnect_timeout_ms,
+        );
+        // the warning has to come before the disconnect
+        reject_out_of_range(
+            "disconnect_notify_start_ms",
+            &mut self.disconnect_notify_start_ms,
+            0..=self.disconnect_timeout_ms,
+            default
+                .disconnect_notify_start_ms
+                .min(self.disconnect_timeout_ms),
+        );
+
+        let simulator = &mut self.network_simulator;
+        let default = default.network_simulator;
+        reject_out_of_range(
+            "network_simulator.latency_ms",
+            &mut simulator.latency_ms,
+            0..=MAX_SIMULATED_DELAY_MS,
+            default.latency_ms,
+        );
+        reject_out_of_range(
+            "network_simulator.jitter_ms",
+            &mut simulator.jitter_ms,
+            0..=MAX_SIMULATED_DELAY_MS,
+            default.jitter_ms,
+        );
+        reject_out_of_range(
+            "network_simulator.packet_loss",
+            &mut simulator.packet_loss,
+            0.0..=1.0,
+            default.packet_loss,
+        );
+        reject_out_of_range(
+            "network_simulator.reorder",
+            &mut simulator.reorder,
+            0.0..=1.0,
+            default.reorder,
+        );
+        self
+    }
+
+    // e.g. `cargo run -- --input-delay 3 --max-prediction 6`
+    pub fn override_with_args(&mut self, args: &[String]) {
+        for pair in args.windows(2) {
+            let (flag, value) = (pair[0].as_str(), pair[1].as_str());
+            let parsed = match flag {
+                "--input-delay" => parse_into(value, &mut self.input_delay),
+                "--max-prediction" => parse_into(value, &mut self.max_prediction),
+                "--desync-detection-interval" => {
+                    parse_into(value, &mut self.desync_detection_interval)
+                }
+                "--disconnect-timeout-ms" => parse_into(value, &mut self.disconnect_timeout_ms),
+                "--disconnect-notify-start-ms" => {
+                    parse_into(value, &mut self.disconnect_notify_start_ms)
+                }
+                "--sim-latency-ms" => parse_into(value, &mut self.network_simulator.latency_ms),
+                "--sim-jitter-ms" => parse_into(value, &mut self.network_simulator.jitter_ms),
+                "--sim-packet-loss" => parse_into(value, &mut self.network_simulator.packet_loss),
+                "--sim-reorder" => parse_into(value, &mut self.network_simulator.reorder),
+                "--sim-seed" => parse_into(value, &mut self.network_simulator.seed),
+                _ => continue,
+            };
+            if !parsed {
+                warn!("ignored invalid value {:?} for {}", value, flag);
+            } else if flag.starts_with("--sim-") {
+                self.network_simulator.enabled = true;
+            }
+        }
+    }
+}
+
+fn parse_into<T: FromStr>(value: &str, target: &mut T) -> bool {
+    match value.parse() {
+        Ok(x) => {
+            *target = x;
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn reject_out_of_range<T: PartialOrd + Copy + Debug>(
+    name: &str,
+    value: &mut T,
+    range: RangeInclusive<T>,
+    default: T,
+) {
+    if !range.contains(value) {
+        warn!(
+            "ggrs {} {:?} is out of {:?}, using {:?}",
+            name, value, range, default
+        );
+        *value = default;
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LaunchArgs(pub Vec<String>);
@@ -0,0 +1,79 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::utils::network_utils::NetAddress;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum SessionEndReason {
+    PlayersLeft,
+    HostLeft,
+    // a lan host gave up on starting, see `LanCancel`
+    Cancelled,
+}
+
+#[derive(Resource, Default)]
+pub struct SessionStatus {
+    // indexed by player handle, `None` is the local player
+    pub players: Vec<Option<NetAddress>>,
+    pub interrupted_peers: HashSet<NetAddress>,
+    pub disconnected_peers: HashSet<NetAddress>,
+    pub ended: Option<SessionEndReason>,
+    pub cancelled: bool,
+    // the frame the next advance should start from, it isn't rolled back so a load that
+    // puts `SimulationFrame` behind it is a rollback
+    pub next_frame: i32,
+    pub rollbacks: u32,
+}
+
+// the frame of the simulated state, rolled back with it
+#[derive(Resource, Reflect, Default, Clone, Copy)]
+#[reflect(Resource)]
+pub struct SimulationFrame(pub i32);
+
+// the simulation frame an online match was decided on, rolled back so a mispredicted
+// death doesn't end it
+#[derive(Resource, Reflect, Default, Clone, Copy)]
+#[reflect(Resource)]
+pub struct MatchEnd {
+    pub frame: Option<i32>,
+}
+
+impl SessionStatus {
+    pub fn remote_players(&self) -> Vec<NetAddress> {
+        self.players.iter().flatten().copied().collect()
+    }
+
+    pub fn can_rematch(&self) -> bool {
+        self.ended.is_none()
+            && !self
+                .players
+                .iter()
+                .flatten()
+                .any(|x| self.disconnected_peers.contains(x))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct RematchStatus {
+    pub local_seed: Option<u64>,
+    pub remote_seeds: HashMap<NetAddress, u64>,
+}
+
+impl RematchStatus {
+    // every player proposes a seed, combining them keeps the result the same on all peers
+    pub fn agreed_seed(&self, remote_players: &[NetAddress]) -> Option<u64> {
+        let local_seed = self.local_seed?;
+        remote_players.iter().try_fold(local_seed, |seed, peer| {
+            self.remote_seeds.get(peer).map(|x| seed ^ x)
+        })
+    }
+
+    pub fn num_waiting(&self, remote_players: &[NetAddress]) -> usize {
+        remote_players
+            .iter()
+            .filter(|x| !self.remote_seeds.contains_key(*x))
+            .count()
+    }
+}
@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+// a screen area controlling one local player, the half left of its center moves left
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct TouchZone {
+    pub local_player: usize,
+    // fractions of the window, y goes up from the bottom, see `touch_position`
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+    // for a player sitting across the tablet, their left is our right
+    #[serde(default)]
+    pub rotated: bool,
+}
+
+impl TouchZone {
+    pub fn full_screen(local_player: usize) -> Self {
+        Self {
+            local_player,
+            left: 0.0,
+            right: 1.0,
+            bottom: 0.0,
+            top: 1.0,
+            rotated: false,
+        }
+    }
+
+    pub fn contains(&self, position: Vec2, window_size: Vec2) -> bool {
+        let x = position.x / window_size.x;
+        let y = position.y / window_size.y;
+        (self.left..=self.right).contains(&x) && (self.bottom..=self.top).contains(&y)
+    }
+
+    pub fn center_x(&self, window_size: Vec2) -> f32 {
+        (self.left + self.right) / 2.0 * window_size.x
+    }
+}
+
+// the `[touch]` table of the app config, a single local player always gets the whole screen
+#[derive(Resource, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TouchZones {
+    pub local_2p: Vec<TouchZone>,
+}
+
+impl Default for TouchZones {
+    fn default() -> Self {
+        Self {
+            local_2p: vec![
+                TouchZone {
+                    top: 0.5,
+                    ..TouchZone::full_screen(0)
+                },
+                TouchZone {
+                    bottom: 0.5,
+                    rotated: true,
+                    ..TouchZone::full_screen(1)
+                },
+            ],
+        }
+    }
+}
+
+impl TouchZones {
+    pub fn zones(&self, num_local_players: usize) -> Vec<TouchZone> {
+        match num_local_players {
+            0 | 1 => vec![TouchZone::full_screen(0)],
+            _ => self.local_2p.clone(),
+        }
+    }
+}
+
+// the `[virtual_buttons]` table of the app config, pixels from the bottom corners of the window
+#[derive(Resource, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct VirtualButtonsConfig {
+    pub enabled: bool,
+    pub size: f32,
+    pub opacity: f32,
+    pub margin: f32,
+    pub bottom: f32,
+}
+
+impl Default for VirtualButtonsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            size: 96.0,
+            opacity: 0.3,
+            margin: 24.0,
+            bottom: 48.0,
+        }
+    }
+}
+
+impl VirtualButtonsConfig {
+    // where the button moving by `move_x` is, y up from the bottom like `touch_position`
+    pub fn button_rect(&self, move_x: f32, window_size: Vec2) -> Rect {
+        let left = if move_x < 0.0 {
+            self.margin
+        } else {
+            window_size.x - self.margin - self.size
+        };
+        Rect::new(left, self.bottom, left + self.size, self.bottom + self.size)
+    }
+
+    // the `move_x` of the button under `position`
+    pub fn button_at(&self, position: Vec2, window_size: Vec2) -> Option<f32> {
+        [-1.0, 1.0]
+            .into_iter()
+            .find(|x| self.button_rect(*x, window_size).contains(position))
+    }
+}
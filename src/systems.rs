@@ -10,7 +10,16 @@ pub mod spring_brick_systems;
 pub mod conveyor_brick_systems;
 pub mod scoreboard_systems;
 pub mod ui;
+pub mod camera_systems;
 pub mod ceiling_systems;
+pub mod coop_systems;
 pub mod floor_stage_systems;
+pub mod gamepad_systems;
+pub mod garbage_systems;
+pub mod ghost_systems;
+pub mod network_diagnostics_systems;
 pub mod network_systems;
+pub mod replay_systems;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lan_systems;
 pub mod wall_systems;
\ No newline at end of file
@@ -0,0 +1,60 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    components::camera::MainCamera,
+    resources::{InGameMode, InGameSetting, StageSize},
+};
+
+// a race puts the shafts side by side, the window grows to fit them where it can
+pub fn resize_window_to_stage(
+    stage_size: Res<StageSize>,
+    mut primary_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if stage_size.num_shafts <= 1 {
+        return;
+    }
+    resize_window_width(&mut primary_query, stage_size.total_width());
+}
+
+pub fn restore_window_size(
+    stage_size: Res<StageSize>,
+    mut primary_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if stage_size.num_shafts <= 1 {
+        return;
+    }
+    resize_window_width(&mut primary_query, stage_size.width);
+}
+
+fn resize_window_width(primary_query: &mut Query<&mut Window, With<PrimaryWindow>>, width: f32) {
+    let Ok(mut window) = primary_query.get_single_mut() else {
+        return;
+    };
+    let height = window.height();
+    window.resize_constraints.max_width = width;
+    window.resolution.set(width, height);
+}
+
+// zooms out when the window didn't get wide enough for every shaft, e.g. in the browser,
+// or is smaller than the fixed online stage
+pub fn fit_camera_to_stage_system(
+    in_game_setting: Res<InGameSetting>,
+    stage_size: Res<StageSize>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<&mut OrthographicProjection, With<MainCamera>>,
+) {
+    if stage_size.num_shafts <= 1 && in_game_setting.mode != InGameMode::Online {
+        return;
+    }
+    let Ok(window) = primary_query.get_single() else {
+        return;
+    };
+    let scale = (stage_size.total_width() / window.width())
+        .max(stage_size.height / window.height())
+        .max(1.0);
+    for mut projection in camera_query.iter_mut() {
+        if projection.scale != scale {
+            projection.scale = scale;
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{
+        normal_brick::NormalBrick,
+        physics::{BoxCollider, Velocity},
+        player::{Dead, Downed, Grounded, Health, Player},
+    },
+    constants::REVIVE_DISTANCE,
+    events::player_events::PlayerLeaveDeadEvent,
+    resources::{InGameSetting, StageSize, TeamHealth},
+    systems::player_systems::is_out_of_stage,
+};
+
+pub fn init_team_health(mut team_health: ResMut<TeamHealth>) {
+    *team_health = TeamHealth::default();
+}
+
+// every living player starts the tick with the team health, whatever a brick did to one
+// of them since then is applied to the whole team
+pub fn team_health_system(
+    in_game_setting: Res<InGameSetting>,
+    mut team_health: ResMut<TeamHealth>,
+    mut player_query: Query<(&mut Health, Option<&Dead>), With<Player>>,
+) {
+    if !in_game_setting.is_coop() {
+        return;
+    }
+    let delta: i32 = player_query
+        .iter()
+        .filter(|(_, maybe_dead)| maybe_dead.is_none())
+        .map(|(health, _)| health.value - team_health.value)
+        .sum();
+    team_health.value = Health::new_player_health().clamp(team_health.value + delta);
+    // bodies don't heal on their own, only a revive brings them back
+    for (mut health, maybe_dead) in player_query.iter_mut() {
+        health.value = match maybe_dead {
+            Some(_) => 0,
+            None => team_health.value,
+        };
+    }
+}
+
+// a co-op player falling out of the shaft goes down on the highest normal brick below
+// their teammates, so someone can still fall next to them
+pub fn player_down_system(
+    mut commands: Commands,
+    in_game_setting: Res<InGameSetting>,
+    stage_size: Res<StageSize>,
+    team_health: Res<TeamHealth>,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut Health,
+            &mut Transform,
+            &mut Velocity,
+            &BoxCollider,
+        ),
+        (With<Player>, Without<Dead>),
+    >,
+    brick_query: Query<(&Transform, &BoxCollider), (With<NormalBrick>, Without<Player>)>,
+) {
+    if !in_game_setting.is_coop() || team_health.value <= 0 {
+        return;
+    }
+    let lowest_teammate = player_query
+        .iter()
+        .filter(|(_, _, transform, _, collider)| {
+            !is_out_of_stage(&stage_size, 0, transform, collider)
+        })
+        .map(|(_, _, transform, _, _)| transform.translation.y)
+        .reduce(f32::min);
+    // the last one standing just dies
+    let Some(lowest_teammate) = lowest_teammate else {
+        return;
+    };
+    let bottom = -stage_size.height.trunc() / 2.0;
+
+    for (entity, mut health, mut transform, mut velocity, collider) in player_query.iter_mut() {
+        if !is_out_of_stage(&stage_size, 0, &transform, collider) {
+            continue;
+        }
+        let maybe_brick = brick_query
+            .iter()
+            .filter(|(brick_transform, _)| {
+                let y = brick_transform.translation.y;
+                y < lowest_teammate - collider.size.y && y > bottom + collider.size.y
+            })
+            .max_by(|a, b| a.0.translation.y.total_cmp(&b.0.translation.y));
+        let Some((brick_transform, brick_collider)) = maybe_brick else {
+            continue;
+        };
+
+        transform.translation.x = brick_transform.translation.x;
+        transform.translation.y =
+            brick_transform.translation.y + (brick_collider.size.y + collider.size.y) / 2.0;
+        *velocity = Velocity(Vec2::new(0.0, -1.0));
+        health.value = 0;
+        commands.entity(entity).insert(Downed {});
+    }
+}
+
+pub fn player_revive_system(
+    mut commands: Commands,
+    in_game_setting: Res<InGameSetting>,
+    team_health: Res<TeamHealth>,
+    mut downed_query: Query<(Entity, &Player, &mut Health, &Transform), With<Downed>>,
+    reviver_query: Query<
+        &Transform,
+        (With<Player>, With<Grounded>, Without<Dead>, Without<Downed>),
+    >,
+    mut revive_events: EventWriter<PlayerLeaveDeadEvent>,
+) {
+    if !in_game_setting.is_coop() || team_health.value <= 0 {
+        return;
+    }
+    for (entity, player, mut health, transform) in downed_query.iter_mut() {
+        // standing on the same brick, close enough
+        let revived = reviver_query.iter().any(|reviver_transform| {
+            let offset = reviver_transform.translation - transform.translation;
+            offset.x.abs() <= REVIVE_DISTANCE && offset.y.abs() < 1.0
+        });
+        if !revived {
+            continue;
+        }
+
+        health.value = team_health.value;
+        commands.entity(entity).remove::<(Dead, Downed)>();
+        revive_events.send(PlayerLeaveDeadEvent {
+            handle: player.handle,
+        });
+    }
+}
@@ -0,0 +1,145 @@
+use bevy::{
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent},
+    prelude::*,
+};
+
+use crate::{
+    components::ui::{BackMainMenuButton, InGameResultMenuUi, GLOBAL_STYLES},
+    constants::AppState,
+    resources::gamepads::{LocalGamepads, MenuFocus},
+};
+
+pub fn gamepad_connection_system(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    mut local_gamepads: ResMut<LocalGamepads>,
+) {
+    for event in connection_events.iter() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => match local_gamepads.assign(event.gamepad) {
+                Some(i) => info!(
+                    "gamepad {:?} ({}) connected as controller {}",
+                    event.gamepad,
+                    info.name,
+                    i + 1
+                ),
+                None => info!(
+                    "gamepad {:?} ({}) connected, all local players have one",
+                    event.gamepad, info.name
+                ),
+            },
+            GamepadConnection::Disconnected => {
+                // the player keeps playing with the keyboard until a controller comes back
+                if let Some(i) = local_gamepads.unassign(event.gamepad) {
+                    warn!(
+                        "gamepad {:?} (controller {}) disconnected",
+                        event.gamepad,
+                        i + 1
+                    );
+                }
+            }
+        }
+    }
+}
+
+// d-pad moves between the visible buttons in reading order, south clicks, east goes back
+#[allow(clippy::type_complexity)]
+pub fn gamepad_menu_navigation_system(
+    state: Res<State<AppState>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut focus: ResMut<MenuFocus>,
+    result_menu_query: Query<(), With<InGameResultMenuUi>>,
+    mut button_query: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &ComputedVisibility,
+            &mut Interaction,
+            &mut BackgroundColor,
+            Option<&BackMainMenuButton>,
+        ),
+        With<Button>,
+    >,
+) {
+    if let Some(entity) = focus.clicked.take() {
+        if let Ok((_, _, _, mut interaction, _, _)) = button_query.get_mut(entity) {
+            if *interaction == Interaction::Clicked {
+                *interaction = Interaction::None;
+            }
+        }
+    }
+    // the d-pad moves the player during a match
+    if state.0 == AppState::InGame && result_menu_query.is_empty() {
+        return;
+    }
+
+    let pressed = |button_type: GamepadButtonType| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type)))
+    };
+    let step: isize =
+        if pressed(GamepadButtonType::DPadDown) || pressed(GamepadButtonType::DPadRight) {
+            1
+        } else if pressed(GamepadButtonType::DPadUp) || pressed(GamepadButtonType::DPadLeft) {
+            -1
+        } else {
+            0
+        };
+    let click = pressed(GamepadButtonType::South);
+    let back = pressed(GamepadButtonType::East);
+
+    let mut buttons: Vec<(Entity, Vec3, bool)> = button_query
+        .iter()
+        .filter(|(_, _, visibility, _, _, _)| visibility.is_visible())
+        .map(|(entity, transform, _, _, _, back_button)| {
+            (entity, transform.translation(), back_button.is_some())
+        })
+        .collect();
+    // ui y grows downwards
+    buttons.sort_by(|a, b| a.1.y.total_cmp(&b.1.y).then(a.1.x.total_cmp(&b.1.x)));
+
+    let previous = focus.focused;
+    let index = previous.and_then(|x| buttons.iter().position(|(entity, _, _)| *entity == x));
+    focus.focused = match (index, step) {
+        (_, _) if buttons.is_empty() => None,
+        (None, 0) => None,
+        (None, _) => Some(buttons[0].0),
+        (Some(i), step) => {
+            let len = buttons.len() as isize;
+            Some(buttons[(i as isize + step).rem_euclid(len) as usize].0)
+        }
+    };
+
+    let target = if back {
+        buttons.iter().find(|x| x.2).map(|x| x.0)
+    } else if click {
+        focus.focused
+    } else {
+        None
+    };
+    if let Some(entity) = target {
+        if let Ok((_, _, _, mut interaction, _, _)) = button_query.get_mut(entity) {
+            *interaction = Interaction::Clicked;
+            focus.clicked = Some(entity);
+        }
+    }
+
+    // highlight like a mouse hover, buttons restore their own color when the interaction changes
+    if previous != focus.focused {
+        if let Some(Ok((_, _, _, interaction, mut background_color, _))) =
+            previous.map(|x| button_query.get_mut(x))
+        {
+            if *interaction == Interaction::None {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+    if let Some(Ok((_, _, _, interaction, mut background_color, _))) =
+        focus.focused.map(|x| button_query.get_mut(x))
+    {
+        if *interaction == Interaction::None {
+            *background_color = GLOBAL_STYLES.hovered_button_color.into();
+        }
+    }
+}
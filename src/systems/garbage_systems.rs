@@ -0,0 +1,221 @@
+use bevy::{prelude::*, sprite::collide_aabb::Collision};
+use bevy_ggrs::RollbackIdProvider;
+
+use crate::{
+    components::{
+        conveyor_brick::ConveyorBrick,
+        fake_brick::{FakeBrick, FakeBrickBundle},
+        garbage::Garbage,
+        nails_brick::{NailsBrick, NailsBrickBundle},
+        normal_brick::NormalBrick,
+        player::{Damaging, GarbageAttack, Health, Player},
+        spring_brick::SpringBrick,
+    },
+    constants::{BRICK_ROW_SPACING, GARBAGE_LANDING_STREAK, GARBAGE_OPACITY, GARBAGE_SPRING_COMBO},
+    events::physics_events::{
+        ConveyorBrickTriggerEnterEvent, FakeBrickTriggerEnterEvent, NormalBrickTriggerEnterEvent,
+        SpringBrickTriggerEnterEvent,
+    },
+    resources::{FakeBrickAssets, InGameSetting, LocalPlayerHandle, NailsBrickAssets, StageSize},
+    systems::in_game_once_systems::nails_brick_transform,
+};
+
+// every few landings in a row, or every spring in a combo, sends a garbage brick to the
+// next living opponent in turn, getting hurt breaks both
+#[allow(clippy::too_many_arguments)]
+pub fn garbage_attack_system(
+    in_game_setting: Res<InGameSetting>,
+    mut normal_brick_events: EventReader<NormalBrickTriggerEnterEvent>,
+    mut fake_brick_events: EventReader<FakeBrickTriggerEnterEvent>,
+    mut conveyor_brick_events: EventReader<ConveyorBrickTriggerEnterEvent>,
+    mut spring_brick_events: EventReader<SpringBrickTriggerEnterEvent>,
+    mut player_query: Query<(
+        Entity,
+        &Player,
+        &Health,
+        &mut GarbageAttack,
+        Option<&Damaging>,
+    )>,
+) {
+    if !in_game_setting.has_garbage_attack() {
+        return;
+    }
+    // (player, landed on a spring)
+    let landings: Vec<(Entity, bool)> = normal_brick_events
+        .iter()
+        .map(|x| &x.0)
+        .chain(fake_brick_events.iter().map(|x| &x.0))
+        .chain(conveyor_brick_events.iter().map(|x| &x.0))
+        .filter(|x| matches!(x.collision, Collision::Top))
+        .map(|x| (x.other, false))
+        .chain(
+            spring_brick_events
+                .iter()
+                .filter(|x| matches!(x.collision, Collision::Top))
+                .map(|x| (x.other, true)),
+        )
+        .collect();
+
+    let mut alive: Vec<usize> = player_query
+        .iter()
+        .filter(|(_, _, health, _, _)| health.value > 0)
+        .map(|(_, player, _, _, _)| player.handle)
+        .collect();
+    alive.sort();
+
+    let mut targets: Vec<usize> = vec![];
+    for (entity, player, health, mut garbage_attack, maybe_damaging) in player_query.iter_mut() {
+        if health.value <= 0 {
+            continue;
+        }
+        if maybe_damaging.is_some() {
+            garbage_attack.landing_streak = 0;
+            garbage_attack.spring_combo = 0;
+            continue;
+        }
+        let opponents: Vec<usize> = alive
+            .iter()
+            .copied()
+            .filter(|x| *x != player.handle)
+            .collect();
+        for (_, is_spring) in landings.iter().filter(|(x, _)| *x == entity) {
+            garbage_attack.landing_streak += 1;
+            garbage_attack.spring_combo = if *is_spring {
+                garbage_attack.spring_combo + 1
+            } else {
+                0
+            };
+            let mut num_garbage = 0;
+            if garbage_attack.landing_streak % GARBAGE_LANDING_STREAK == 0 {
+                num_garbage += 1;
+            }
+            if garbage_attack.spring_combo >= GARBAGE_SPRING_COMBO {
+                num_garbage += 1;
+            }
+            if opponents.is_empty() {
+                continue;
+            }
+            for _ in 0..num_garbage {
+                targets.push(opponents[garbage_attack.sent as usize % opponents.len()]);
+                garbage_attack.sent += 1;
+            }
+        }
+    }
+
+    for (_, player, _, mut garbage_attack, _) in player_query.iter_mut() {
+        garbage_attack.pending += targets.iter().filter(|x| **x == player.handle).count() as u32;
+    }
+}
+
+// garbage goes into the first row below the shaft that doesn't have any for that player
+// yet, beside the brick already there. where it lands only depends on the seed and how
+// much they already got so every peer puts it in the same place, it is a rollback entity
+// so loading a frame from before a mispredicted garbage despawns it
+#[allow(clippy::too_many_arguments)]
+pub fn inject_garbage_system(
+    mut commands: Commands,
+    mut maybe_rip: Option<ResMut<RollbackIdProvider>>,
+    in_game_setting: Res<InGameSetting>,
+    stage_size: Res<StageSize>,
+    maybe_local_player_handle: Option<Res<LocalPlayerHandle>>,
+    nails_brick_assets: Res<NailsBrickAssets>,
+    fake_brick_assets: Res<FakeBrickAssets>,
+    mut player_query: Query<(&Player, &Health, &mut GarbageAttack)>,
+    row_query: Query<
+        &Transform,
+        (
+            Or<(
+                With<NormalBrick>,
+                With<FakeBrick>,
+                With<ConveyorBrick>,
+                With<SpringBrick>,
+            )>,
+            Without<Garbage>,
+        ),
+    >,
+    nails_brick_query: Query<&Transform, (With<NailsBrick>, Without<Garbage>)>,
+    garbage_query: Query<(&Transform, &Garbage)>,
+) {
+    if !in_game_setting.has_garbage_attack() {
+        return;
+    }
+    let height = stage_size.height.trunc();
+    let width = stage_size.width.trunc() as i32;
+    let min_x = -(width / 2) + 50;
+    let max_x = (width / 2) - 50;
+    let spacing = BRICK_ROW_SPACING as f32;
+    // the widest brick, garbage this far from the row's brick doesn't overlap it
+    let brick_width = 97;
+    // the highest row that is not on screen yet
+    let Some(next_row_y) = row_query
+        .iter()
+        .map(|transform| transform.translation.y)
+        .filter(|y| *y < -height / 2.0)
+        .reduce(f32::max)
+    else {
+        return;
+    };
+
+    for (player, health, mut garbage_attack) in player_query.iter_mut() {
+        if garbage_attack.pending == 0 || health.value <= 0 {
+            continue;
+        }
+        let target = player.handle;
+        let Some(row_y) = (0..)
+            .map(|i| next_row_y - i as f32 * spacing)
+            .find(|row_y| {
+                !garbage_query.iter().any(|(transform, garbage)| {
+                    garbage.target == target
+                        && (transform.translation.y - row_y).abs() < spacing / 2.0
+                })
+            })
+        else {
+            continue;
+        };
+
+        let row_brick_x = row_query
+            .iter()
+            .chain(nails_brick_query.iter())
+            .find(|transform| (transform.translation.y - row_y).abs() < spacing / 2.0)
+            .map(|transform| transform.translation.x as i32);
+
+        let rng = fastrand::Rng::with_seed(
+            in_game_setting.seed ^ (((target as u64) << 32) | garbage_attack.received as u64),
+        );
+        // skips over the row's brick, the range is the row without it
+        let x = match row_brick_x {
+            Some(brick_x) if max_x - min_x > 2 * brick_width => {
+                let x = rng.i32(min_x..max_x - 2 * brick_width);
+                if x > brick_x - brick_width {
+                    x + 2 * brick_width
+                } else {
+                    x
+                }
+            }
+            _ => rng.i32(min_x..max_x),
+        };
+        let transform = Transform::from_xyz(x as f32, row_y, 0.0);
+        // the target sees their garbage like any brick, the others barely
+        let color = match maybe_local_player_handle.as_deref() {
+            Some(local) if !in_game_setting.is_spectator && local.0 != target => {
+                Color::rgba(1.0, 1.0, 1.0, GARBAGE_OPACITY)
+            }
+            _ => Color::WHITE,
+        };
+        let mut garbage = if rng.bool() {
+            let mut bundle =
+                NailsBrickBundle::new(nails_brick_transform(transform), &nails_brick_assets);
+            bundle.sprites.sprite.color = color;
+            commands.spawn((bundle, Garbage { target }))
+        } else {
+            let mut bundle = FakeBrickBundle::new(transform, &fake_brick_assets);
+            bundle.sprites.sprite.color = color;
+            commands.spawn((bundle, Garbage { target }))
+        };
+        if let Some(rip) = maybe_rip.as_deref_mut() {
+            garbage.insert(rip.next());
+        }
+        garbage_attack.pending -= 1;
+        garbage_attack.received += 1;
+    }
+}
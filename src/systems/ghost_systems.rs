@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{
+        animation::{Animation, AnimationState},
+        player::{Ghost, PlayerAnimations},
+    },
+    constants::{AppState, BEST_REPLAY_PATH, GHOST_OPACITY},
+    resources::{
+        replay::{GhostRace, Replay},
+        session_config::LaunchArgs,
+        InGameMode, InGameSetting, PlayerAssets,
+    },
+};
+
+// a friend's run from `--ghost <file>`, otherwise the personal best
+pub fn start_ghost_race(
+    commands: &mut Commands,
+    launch_args: &LaunchArgs,
+    in_game_setting: &mut InGameSetting,
+    next_state: &mut NextState<AppState>,
+) {
+    let path = launch_args
+        .0
+        .windows(2)
+        .find(|pair| pair[0] == "--ghost")
+        .map(|pair| pair[1].as_str())
+        .unwrap_or(BEST_REPLAY_PATH);
+    let replay = match Replay::load(path) {
+        Ok(replay) => replay,
+        Err(err) => {
+            warn!("no ghost to race in {}: {}", path, err);
+            return;
+        }
+    };
+    if replay.num_players != 1 || replay.track.is_empty() {
+        warn!("{} is not a single player run with a ghost track", path);
+        return;
+    }
+
+    info!("racing ghost {}, score {}", path, replay.score);
+    in_game_setting.set_offline_1p();
+    // same stage as the ghost, the stage size comes along in `init_stage_size`
+    in_game_setting.seed = replay.seed;
+    commands.insert_resource(GhostRace::new(replay));
+    next_state.set(AppState::InGame);
+}
+
+pub fn close_ghost_race(mut commands: Commands) {
+    commands.remove_resource::<GhostRace>();
+}
+
+pub fn spawn_ghost(
+    mut commands: Commands,
+    in_game_setting: Res<InGameSetting>,
+    maybe_ghost_race: Option<ResMut<GhostRace>>,
+    player_assets: Res<PlayerAssets>,
+) {
+    let Some(mut ghost_race) = maybe_ghost_race else {
+        return;
+    };
+    if in_game_setting.mode != InGameMode::Offline {
+        return;
+    }
+    ghost_race.tick = 0;
+    let Some(start) = ghost_race.replay.track.first() else {
+        return;
+    };
+
+    let animations = PlayerAnimations::default();
+    commands.spawn((
+        SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                color: Color::rgba(1.0, 1.0, 1.0, GHOST_OPACITY),
+                ..default()
+            },
+            texture_atlas: player_assets.sprite_sheet.clone(),
+            // just behind the live player
+            transform: Transform::from_translation(start.extend(1.5)),
+            ..default()
+        },
+        animations.idle.clone(),
+        AnimationState::default(),
+        animations,
+        Ghost {},
+    ));
+}
+
+// one recorded position per tick keeps the ghost in step with the live player
+pub fn ghost_follow_system(
+    maybe_ghost_race: Option<ResMut<GhostRace>>,
+    mut ghost_query: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &mut Animation,
+            &mut AnimationState,
+            &PlayerAnimations,
+        ),
+        With<Ghost>,
+    >,
+) {
+    let Some(mut ghost_race) = maybe_ghost_race else {
+        return;
+    };
+    let tick = ghost_race.tick;
+    ghost_race.tick += 1;
+
+    for (mut transform, mut visibility, mut animation, mut animation_state, animations) in
+        ghost_query.iter_mut()
+    {
+        // the recorded run died here
+        let Some(position) = ghost_race.replay.track.get(tick) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        let dx = position.x - transform.translation.x;
+        let next_animation = if dx > 0.0 {
+            &animations.right_run
+        } else if dx < 0.0 {
+            &animations.left_run
+        } else {
+            &animations.idle
+        };
+        if *next_animation != *animation {
+            animation_state.reset();
+            animation.clone_from(next_animation);
+        }
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
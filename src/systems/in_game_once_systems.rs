@@ -1,6 +1,7 @@
 use std::ops::RangeInclusive;
 
 use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_ggrs::RollbackIdProvider;
 use bevy_kira_audio::prelude::*;
 
 use crate::{
@@ -16,10 +17,15 @@ use crate::{
         spring_brick::SpringBrickBundle,
         wall::{WallBundle, WallPositionReset},
     },
-    constants::{CELLING_HEIGHT, IN_GAME_UI_APP_BAR_HEIGHT, WALL_HEIGHT, WALL_WIDTH},
+    constants::{
+        BRICK_ROW_SPACING, CELLING_HEIGHT, IN_GAME_UI_APP_BAR_HEIGHT, ONLINE_STAGE_HEIGHT,
+        ONLINE_STAGE_WIDTH, WALL_HEIGHT, WALL_WIDTH,
+    },
     resources::{
-        CeilingAssets, ConveyorBrickAssets, FakeBrickAssets, InGameSetting, NailsBrickAssets,
-        NormalBrickAssets, PlayerAssets, SpringBrickAssets, UiAssets, WallAssets,
+        replay::{GhostRace, ReplayPlayback},
+        CeilingAssets, ConveyorBrickAssets, FakeBrickAssets, InGameMode, InGameSetting,
+        NailsBrickAssets, NormalBrickAssets, PlayerAssets, SpringBrickAssets, StageSize, UiAssets,
+        WallAssets,
     },
 };
 
@@ -34,6 +40,34 @@ pub fn despawn_in_game_all(
     }
 }
 
+// a replay or a ghost brings the size it was recorded with, online it can't depend on
+// anyone's window
+pub fn init_stage_size(
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    in_game_setting: Res<InGameSetting>,
+    maybe_playback: Option<Res<ReplayPlayback>>,
+    maybe_ghost_race: Option<Res<GhostRace>>,
+    mut stage_size: ResMut<StageSize>,
+) {
+    let maybe_replay = match (&maybe_playback, &maybe_ghost_race) {
+        (Some(playback), _) => Some(&playback.replay),
+        (None, Some(ghost_race)) => Some(&ghost_race.replay),
+        (None, None) => None,
+    };
+    if let Some(replay) = maybe_replay {
+        stage_size.width = replay.stage_width as f32;
+        stage_size.height = replay.stage_height as f32;
+    } else if in_game_setting.mode == InGameMode::Online {
+        stage_size.width = ONLINE_STAGE_WIDTH;
+        stage_size.height = ONLINE_STAGE_HEIGHT;
+    } else if let Ok(window) = primary_query.get_single() {
+        // whole pixels like a replay stores them, so it plays the same stage again
+        stage_size.width = window.width().trunc();
+        stage_size.height = window.height().trunc();
+    }
+    stage_size.num_shafts = in_game_setting.num_shafts();
+}
+
 pub fn spawn_camera(mut commands: Commands) {
     commands.spawn((Camera2dBundle::default(), MainCamera));
 }
@@ -41,21 +75,43 @@ pub fn spawn_camera(mut commands: Commands) {
 pub fn spawn_players(
     mut commands: Commands,
     in_game_setting: Res<InGameSetting>,
+    stage_size: Res<StageSize>,
     player_assets: Res<PlayerAssets>,
     ui_assets: Res<UiAssets>,
+    mut maybe_rip: Option<ResMut<RollbackIdProvider>>,
 ) {
     for handle in 0..in_game_setting.num_players {
-        let player1_transform = Transform::from_xyz(32.0 * handle as f32, 200.0, 2.0);
-        commands.spawn(PlayerBundle::new(handle, player1_transform, &player_assets));
+        // racers start alone in the middle of their own shaft
+        let x = if in_game_setting.is_race() {
+            stage_size.shaft_x(in_game_setting.player_shaft(handle))
+        } else {
+            player_spawn_x(handle, in_game_setting.num_players)
+        };
+        let player_transform = Transform::from_xyz(x, 200.0, 2.0);
+        let mut player =
+            commands.spawn(PlayerBundle::new(handle, player_transform, &player_assets));
+        // online, ggrs saves and loads the registered components of rollback entities
+        if let Some(rip) = maybe_rip.as_deref_mut() {
+            player.insert(rip.next());
+        }
     }
 
     build_in_game_ui(
         &mut commands,
         &ui_assets,
         (0..in_game_setting.num_players).into_iter().collect(),
+        // everyone watching this screen wants to compare scores
+        in_game_setting.num_local_players() != 1,
+        in_game_setting.is_race(),
     );
 }
 
+fn player_spawn_x(handle: usize, num_players: usize) -> f32 {
+    let spacing = 48.0;
+    let center = (num_players.max(1) - 1) as f32 / 2.0;
+    (handle as f32 - center) * spacing
+}
+
 pub fn spawn_bricks(
     mut commands: Commands,
     normal_brick_assets: Res<NormalBrickAssets>,
@@ -141,74 +197,112 @@ impl BrickProbability {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_bricks_2(
     mut commands: Commands,
-    primary_query: Query<&Window, With<PrimaryWindow>>,
+    in_game_setting: Res<InGameSetting>,
+    stage_size: Res<StageSize>,
     normal_brick_assets: Res<NormalBrickAssets>,
     nails_brick_assets: Res<NailsBrickAssets>,
     fake_brick_assets: Res<FakeBrickAssets>,
     spring_brick_assets: Res<SpringBrickAssets>,
     conveyor_brick_assets: Res<ConveyorBrickAssets>,
+    mut maybe_rip: Option<ResMut<RollbackIdProvider>>,
 ) {
-    let pos_rng = fastrand::Rng::with_seed(5);
-    let mut brick_type_rng = fastrand::Rng::with_seed(3);
-    let conveyor_dir_rng = fastrand::Rng::with_seed(4);
+    // every shaft gets the same bricks so a race is fair
+    for shaft in 0..stage_size.num_shafts.max(1) {
+        spawn_shaft_bricks(
+            &mut commands,
+            in_game_setting.seed,
+            &stage_size,
+            stage_size.shaft_x(shaft),
+            &normal_brick_assets,
+            &nails_brick_assets,
+            &fake_brick_assets,
+            &spring_brick_assets,
+            &conveyor_brick_assets,
+            maybe_rip.as_deref_mut(),
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_shaft_bricks(
+    commands: &mut Commands,
+    seed: u64,
+    stage_size: &StageSize,
+    offset_x: f32,
+    normal_brick_assets: &NormalBrickAssets,
+    nails_brick_assets: &NailsBrickAssets,
+    fake_brick_assets: &FakeBrickAssets,
+    spring_brick_assets: &SpringBrickAssets,
+    conveyor_brick_assets: &ConveyorBrickAssets,
+    mut maybe_rip: Option<&mut RollbackIdProvider>,
+) {
+    // the default seed keeps the original 5, 3, 4 stage layout
+    let pos_rng = fastrand::Rng::with_seed(seed);
+    let mut brick_type_rng = fastrand::Rng::with_seed(seed.wrapping_sub(2));
+    let conveyor_dir_rng = fastrand::Rng::with_seed(seed.wrapping_sub(1));
 
     let brick_prob = BrickProbability::new();
-    if let Ok(window) = primary_query.get_single() {
-        let height = window.height().trunc() as i32;
-        let width = window.width().trunc() as i32;
-        let min_x = (-1 * (width / 2)) + 50;
-        let max_x = (width / 2) - 50;
-        let max_y = height / 2;
-        for i in 0..500 {
-            let x = pos_rng.i32(min_x..max_x);
-            let y = max_y - i * 55;
-            let transform = Transform::from_xyz(x as f32, y as f32, 0.0);
+    let height = stage_size.height.trunc() as i32;
+    let width = stage_size.width.trunc() as i32;
+    let min_x = (-1 * (width / 2)) + 50;
+    let max_x = (width / 2) - 50;
+    let max_y = height / 2;
+    for i in 0..500 {
+        let x = pos_rng.i32(min_x..max_x);
+        let y = max_y - i * BRICK_ROW_SPACING;
+        let transform = Transform::from_xyz(x as f32 + offset_x, y as f32, 0.0);
 
-            let btype = brick_prob.sample(&mut brick_type_rng);
-            match btype {
-                BrickType::Normal => {
-                    commands.spawn(NormalBrickBundle::new(transform, &normal_brick_assets));
-                }
-                BrickType::Fake => {
-                    commands.spawn(FakeBrickBundle::new(transform, &fake_brick_assets));
-                }
-                BrickType::Nails => {
-                    let nail_transform = transform
-                        .clone()
-                        .with_translation(transform.translation + Vec3::new(0.0, 15.5, 0.0));
-                    commands.spawn(NailsBrickBundle::new(nail_transform, &nails_brick_assets));
-                }
-                BrickType::Conveyor => {
-                    let dir = if conveyor_dir_rng.bool() {
-                        ConveyorDirection::Left
-                    } else {
-                        ConveyorDirection::Right
-                    };
-                    commands.spawn(ConveyorBrickBundle::new(
-                        dir,
-                        transform,
-                        &conveyor_brick_assets,
-                    ));
-                }
-                BrickType::Spring => {
-                    commands.spawn(SpringBrickBundle::new(transform, &spring_brick_assets));
-                }
+        let btype = brick_prob.sample(&mut brick_type_rng);
+        let mut brick = match btype {
+            BrickType::Normal => {
+                commands.spawn(NormalBrickBundle::new(transform, normal_brick_assets))
+            }
+            BrickType::Fake => commands.spawn(FakeBrickBundle::new(transform, fake_brick_assets)),
+            BrickType::Nails => commands.spawn(NailsBrickBundle::new(
+                nails_brick_transform(transform),
+                nails_brick_assets,
+            )),
+            BrickType::Conveyor => {
+                let dir = if conveyor_dir_rng.bool() {
+                    ConveyorDirection::Left
+                } else {
+                    ConveyorDirection::Right
+                };
+                commands.spawn(ConveyorBrickBundle::new(
+                    dir,
+                    transform,
+                    conveyor_brick_assets,
+                ))
+            }
+            BrickType::Spring => {
+                commands.spawn(SpringBrickBundle::new(transform, spring_brick_assets))
             }
+        };
+        // bricks rise every tick, online their positions are rolled back too
+        if let Some(rip) = maybe_rip.as_deref_mut() {
+            brick.insert(rip.next());
         }
     }
 }
 
+// nails stick out of the row they are in
+pub fn nails_brick_transform(row_transform: Transform) -> Transform {
+    row_transform.with_translation(row_transform.translation + Vec3::new(0.0, 15.5, 0.0))
+}
+
 pub fn spawn_walls(
     mut commands: Commands,
-    primary_query: Query<&Window, With<PrimaryWindow>>,
+    stage_size: Res<StageSize>,
     wall_assets: Res<WallAssets>,
 ) {
-    if let Ok(window) = primary_query.get_single() {
-        let width = window.width();
+    let wall_x = stage_size.width / 2.0 - (WALL_WIDTH / 2.0);
+    for shaft in 0..stage_size.num_shafts.max(1) {
+        let offset_x = stage_size.shaft_x(shaft);
 
-        let right_transform = Transform::from_xyz(width / 2.0 - (WALL_WIDTH / 2.0), 0.0, 0.0);
+        let right_transform = Transform::from_xyz(offset_x + wall_x, 0.0, 0.0);
         commands
             .spawn(WallBundle::new(right_transform, &wall_assets))
             .insert(Velocity(Vec2::new(0.0, 1.0)))
@@ -217,8 +311,7 @@ pub fn spawn_walls(
                 target_y: WALL_HEIGHT / 2.0,
             });
 
-        let right_transform2 =
-            Transform::from_xyz(width / 2.0 - (WALL_WIDTH / 2.0), -WALL_HEIGHT, 0.0);
+        let right_transform2 = Transform::from_xyz(offset_x + wall_x, -WALL_HEIGHT, 0.0);
         commands
             .spawn(WallBundle::new(right_transform2, &wall_assets))
             .insert(Velocity(Vec2::new(0.0, 1.0)))
@@ -227,7 +320,7 @@ pub fn spawn_walls(
                 target_y: 0.0,
             });
 
-        let left_transform = Transform::from_xyz(-1.0 * right_transform.translation.x, 0.0, 0.0);
+        let left_transform = Transform::from_xyz(offset_x - wall_x, 0.0, 0.0);
         commands
             .spawn(WallBundle::new(left_transform, &wall_assets))
             .insert(Velocity(Vec2::new(0.0, 1.0)))
@@ -236,11 +329,8 @@ pub fn spawn_walls(
                 target_y: WALL_HEIGHT / 2.0,
             });
 
-        let left_transform2 = Transform::from_xyz(
-            -1.0 * right_transform.translation.x,
-            right_transform2.translation.y,
-            0.0,
-        );
+        let left_transform2 =
+            Transform::from_xyz(offset_x - wall_x, right_transform2.translation.y, 0.0);
         commands
             .spawn(WallBundle::new(left_transform2, &wall_assets))
             .insert(Velocity(Vec2::new(0.0, 1.0)))
@@ -253,17 +343,18 @@ pub fn spawn_walls(
 
 pub fn spawn_ceiling(
     mut commands: Commands,
-    primary_query: Query<&Window, With<PrimaryWindow>>,
+    stage_size: Res<StageSize>,
     ceiling_assets: Res<CeilingAssets>,
 ) {
-    let window = primary_query.get_single().unwrap();
-    let height = window.height();
-    let transform = Transform::from_xyz(
-        0.0,
-        (height / 2.0) - IN_GAME_UI_APP_BAR_HEIGHT - (CELLING_HEIGHT / 2.0),
-        0.0,
-    );
-    commands.spawn(CeilingBundle::new(transform, &ceiling_assets));
+    let height = stage_size.height;
+    for shaft in 0..stage_size.num_shafts.max(1) {
+        let transform = Transform::from_xyz(
+            stage_size.shaft_x(shaft),
+            (height / 2.0) - IN_GAME_UI_APP_BAR_HEIGHT - (CELLING_HEIGHT / 2.0),
+            0.0,
+        );
+        commands.spawn(CeilingBundle::new(transform, &ceiling_assets));
+    }
 }
 
 pub fn play_background_sound(asset_server: Res<AssetServer>, audio: Res<Audio>) {
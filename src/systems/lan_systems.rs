@@ -0,0 +1,314 @@
+use std::{
+    iter,
+    net::{Ipv4Addr, SocketAddr},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    constants::{
+        AppState, LAN_ANNOUNCE_INTERVAL, LAN_DISCOVERY_PORT, LAN_HOST_TIMEOUT, LAN_JOIN_INTERVAL,
+        LAN_CANCEL_REPEAT, LAN_PORT, LAN_START_RESEND_INTERVAL, LAN_START_TIMEOUT,
+    },
+    resources::{
+        lan_lobby::{LanDiscovery, LanHost, LanLobby, LanLobbyMode},
+        matchmaking::MatchmakingSetting,
+        session_config::GgrsSessionConfig,
+        InGameSetting,
+    },
+    systems::network_systems::start_ggrs_session,
+    utils::network_utils::{GgrsChannel, LanSocket, NetAddress, NetMessage},
+};
+
+pub fn start_lan_lobby(mut commands: Commands) {
+    // only one game per machine can listen for hosts, direct connect still works for the others
+    let socket = match LanSocket::bind(LAN_DISCOVERY_PORT) {
+        Ok(socket) => Some(socket),
+        Err(err) => {
+            warn!("lan discovery disabled: {}", err);
+            None
+        }
+    };
+    commands.insert_resource(LanDiscovery {
+        socket,
+        hosts: Vec::new(),
+    });
+    commands.insert_resource(LanLobby::default());
+}
+
+pub fn close_lan_lobby(mut commands: Commands) {
+    commands.remove_resource::<LanDiscovery>();
+    commands.remove_resource::<LanLobby>();
+}
+
+pub fn host_lan_game(commands: &mut Commands, lobby: &mut LanLobby) {
+    match LanSocket::bind(LAN_PORT) {
+        Ok(socket) => {
+            info!("hosting lan game on port {}", LAN_PORT);
+            commands.insert_resource(socket);
+            lobby.mode = LanLobbyMode::Hosting {
+                joiners: Vec::new(),
+            };
+            lobby.last_sent_at = f32::NEG_INFINITY;
+            lobby.error = None;
+        }
+        Err(err) => {
+            warn!("failed to bind port {}: {}", LAN_PORT, err);
+            lobby.error = Some(format!("Port {} is already in use", LAN_PORT));
+        }
+    }
+}
+
+pub fn join_lan_game(commands: &mut Commands, lobby: &mut LanLobby, host: SocketAddr) {
+    match LanSocket::bind(0) {
+        Ok(socket) => {
+            info!("joining lan game at {}", host);
+            commands.insert_resource(socket);
+            lobby.mode = LanLobbyMode::Joining { host };
+            lobby.last_sent_at = f32::NEG_INFINITY;
+            lobby.error = None;
+        }
+        Err(err) => {
+            warn!("failed to bind lan socket: {}", err);
+            lobby.error = Some("Can't open a network socket".to_string());
+        }
+    }
+}
+
+pub fn discover_lan_hosts_system(time: Res<Time>, mut discovery: ResMut<LanDiscovery>) {
+    let Some(socket) = discovery.socket.clone() else {
+        return;
+    };
+    let now = time.elapsed_seconds();
+
+    // only touch change detection when the host list shown in the lobby changes
+    let mut changed = false;
+    let hosts = &mut discovery.bypass_change_detection().hosts;
+    for (addr, message) in socket.receive_messages() {
+        let NetMessage::LanAnnounce {
+            num_players,
+            num_joined,
+            rules,
+        } = message
+        else {
+            continue;
+        };
+        match hosts.iter_mut().find(|x| x.addr == addr) {
+            Some(host) => {
+                changed |= host.num_players != num_players
+                    || host.num_joined != num_joined
+                    || host.rules != rules;
+                host.num_players = num_players;
+                host.num_joined = num_joined;
+                host.rules = rules;
+                host.last_seen_at = now;
+            }
+            None => {
+                hosts.push(LanHost {
+                    addr,
+                    num_players,
+                    num_joined,
+                    rules,
+                    last_seen_at: now,
+                });
+                changed = true;
+            }
+        }
+    }
+    let num_hosts = hosts.len();
+    hosts.retain(|x| now - x.last_seen_at < LAN_HOST_TIMEOUT);
+    changed |= hosts.len() != num_hosts;
+
+    if changed {
+        discovery.set_changed();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn lan_lobby_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut in_game_setting: ResMut<InGameSetting>,
+    session_config: Res<GgrsSessionConfig>,
+    matchmaking_setting: Res<MatchmakingSetting>,
+    mut lobby: ResMut<LanLobby>,
+    maybe_socket: Option<Res<LanSocket>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(socket) = maybe_socket else {
+        return;
+    };
+    let now = time.elapsed_seconds();
+    let messages = socket.receive_messages();
+    let lobby = &mut *lobby;
+
+    match &mut lobby.mode {
+        LanLobbyMode::Idle => {}
+        LanLobbyMode::Hosting { joiners } => {
+            let num_players = matchmaking_setting.num_players;
+            for (addr, message) in messages {
+                if matches!(message, NetMessage::LanJoin)
+                    && !joiners.contains(&addr)
+                    && joiners.len() + 1 < num_players
+                {
+                    info!("{} joined the lan game", addr);
+                    joiners.push(addr);
+                }
+            }
+
+            if joiners.len() + 1 >= num_players {
+                info!("All players have joined, starting");
+                lobby.mode = LanLobbyMode::Starting {
+                    joiners: joiners.clone(),
+                    acked: Vec::new(),
+                    started_at: now,
+                };
+                lobby.last_sent_at = f32::NEG_INFINITY;
+                return;
+            }
+
+            if now - lobby.last_sent_at >= LAN_ANNOUNCE_INTERVAL {
+                lobby.last_sent_at = now;
+                socket.send_message(
+                    SocketAddr::from((Ipv4Addr::BROADCAST, LAN_DISCOVERY_PORT)),
+                    &NetMessage::LanAnnounce {
+                        num_players,
+                        num_joined: joiners.len() + 1,
+                        rules: in_game_setting.rules,
+                    },
+                );
+            }
+        }
+        LanLobbyMode::Starting {
+            joiners,
+            acked,
+            started_at,
+        } => {
+            for (addr, message) in messages {
+                if matches!(message, NetMessage::LanStartAck)
+                    && joiners.contains(&addr)
+                    && !acked.contains(&addr)
+                {
+                    acked.push(addr);
+                }
+            }
+
+            // the host always plays with handle 0
+            let players: Vec<Option<SocketAddr>> = iter::once(None)
+                .chain(joiners.iter().copied().map(Some))
+                .collect();
+            let timed_out = now - *started_at >= LAN_START_TIMEOUT;
+            if acked.len() < joiners.len() && !timed_out {
+                if now - lobby.last_sent_at >= LAN_START_RESEND_INTERVAL {
+                    lobby.last_sent_at = now;
+                    for (i, addr) in joiners.iter().enumerate() {
+                        if acked.contains(addr) {
+                            continue;
+                        }
+                        let message = NetMessage::LanStart {
+                            handle: i + 1,
+                            players: players.clone(),
+                            rules: in_game_setting.rules,
+                        };
+                        socket.send_message(*addr, &message);
+                    }
+                }
+                return;
+            }
+            if timed_out {
+                // a ggrs session never gives up on a peer that didn't synchronize, so the
+                // game is called off, including for the joiners already in it
+                warn!(
+                    "only {} of {} joiners acknowledged the start, calling the game off",
+                    acked.len(),
+                    joiners.len()
+                );
+                for addr in acked.iter() {
+                    for _ in 0..LAN_CANCEL_REPEAT {
+                        socket.send_message(*addr, &NetMessage::LanCancel);
+                    }
+                }
+                commands.remove_resource::<LanSocket>();
+                lobby.mode = LanLobbyMode::Idle;
+                lobby.error = Some("A player didn't answer, the game was called off".to_string());
+                return;
+            }
+
+            info!("All players have joined, going in-game");
+            in_game_setting.set_online(players.len());
+            let players = players
+                .into_iter()
+                .map(|x| x.map(NetAddress::Udp))
+                .collect();
+            start_lan_session(
+                &mut commands,
+                &in_game_setting,
+                &session_config,
+                &socket,
+                players,
+            );
+            next_state.set(AppState::InGame);
+        }
+        LanLobbyMode::Joining { host } => {
+            let host = *host;
+            for (addr, message) in messages {
+                let NetMessage::LanStart {
+                    handle,
+                    players,
+                    rules,
+                } = message
+                else {
+                    continue;
+                };
+                if addr != host || handle >= players.len() {
+                    continue;
+                }
+
+                socket.send_message(host, &NetMessage::LanStartAck);
+                info!("lan game started, playing as {}", handle);
+                let players = players
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, x)| (i != handle).then_some(NetAddress::Udp(x.unwrap_or(host))))
+                    .collect::<Vec<_>>();
+                // the host picks the rules
+                in_game_setting.rules = rules;
+                in_game_setting.set_online(players.len());
+                start_lan_session(
+                    &mut commands,
+                    &in_game_setting,
+                    &session_config,
+                    &socket,
+                    players,
+                );
+                next_state.set(AppState::InGame);
+                return;
+            }
+
+            if now - lobby.last_sent_at >= LAN_JOIN_INTERVAL {
+                lobby.last_sent_at = now;
+                socket.send_message(host, &NetMessage::LanJoin);
+            }
+        }
+    }
+}
+
+fn start_lan_session(
+    commands: &mut Commands,
+    in_game_setting: &InGameSetting,
+    session_config: &GgrsSessionConfig,
+    socket: &LanSocket,
+    players: Vec<Option<NetAddress>>,
+) {
+    socket.open_ggrs();
+    let channel = GgrsChannel::Lan(socket.clone());
+    commands.insert_resource(channel.clone());
+    start_ggrs_session(
+        commands,
+        in_game_setting,
+        session_config,
+        &players,
+        &[],
+        channel,
+    );
+}
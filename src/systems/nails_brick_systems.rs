@@ -3,6 +3,7 @@ use bevy_kira_audio::prelude::*;
 
 use crate::{
     components::{
+        garbage::Garbage,
         nails_brick::NailsBrickHitbox,
         physics::BoxCollider,
         player::{Damaging, DamagingTimer, Dead, Health, Player},
@@ -14,20 +15,23 @@ use crate::{
 pub fn player_nails_hitbox_system(
     mut commands: Commands,
     mut player_query: Query<
-        (Entity, &Transform, &BoxCollider, &mut Health),
+        (Entity, &Player, &Transform, &BoxCollider, &mut Health),
         (With<Player>, Without<Damaging>, Without<Dead>),
     >,
-    collider_query: Query<(&Transform, &NailsBrickHitbox)>,
+    collider_query: Query<(&Transform, &NailsBrickHitbox, Option<&Garbage>)>,
     nails_brick_assets: Res<NailsBrickAssets>,
     audio: Res<Audio>,
 ) {
-    for (player_entity, player_transform, player_collider, mut player_health) in
+    for (player_entity, player, player_transform, player_collider, mut player_health) in
         player_query.iter_mut()
     {
         let player_translation = get_collider_translation(player_transform, player_collider);
         let player_size = get_collider_size(player_transform, player_collider);
 
-        for (transform, collider) in collider_query.iter() {
+        for (transform, collider, maybe_garbage) in collider_query.iter() {
+            if maybe_garbage.map_or(false, |x| x.target != player.handle) {
+                continue;
+            }
             let collider_translation = get_collider_translation(transform, collider);
             let collider_size = get_collider_size(transform, collider);
 
@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+use bevy_ggrs::Session;
+
+use crate::{
+    constants::{GgrsConfig, NETWORK_DIAGNOSTICS_KEY},
+    resources::{
+        network_diagnostics::NetworkDiagnostics,
+        session_status::{SessionStatus, SimulationFrame},
+    },
+};
+
+pub fn toggle_network_diagnostics_system(
+    keys: Res<Input<KeyCode>>,
+    mut diagnostics: ResMut<NetworkDiagnostics>,
+) {
+    if keys.just_pressed(NETWORK_DIAGNOSTICS_KEY) {
+        diagnostics.visible = !diagnostics.visible;
+    }
+}
+
+// runs in the ggrs schedule once per advance, resimulated frames included
+pub fn advance_simulation_frame_system(
+    mut frame: ResMut<SimulationFrame>,
+    maybe_session_status: Option<ResMut<SessionStatus>>,
+) {
+    let Some(mut session_status) = maybe_session_status else {
+        return;
+    };
+    if frame.0 < session_status.next_frame {
+        session_status.rollbacks += 1;
+    }
+    frame.0 += 1;
+    session_status.next_frame = frame.0;
+}
+
+pub fn update_network_diagnostics_system(
+    time: Res<Time>,
+    maybe_session: Option<Res<Session<GgrsConfig>>>,
+    maybe_session_status: Option<Res<SessionStatus>>,
+    mut diagnostics: ResMut<NetworkDiagnostics>,
+) {
+    let (Some(Session::P2PSession(session)), Some(session_status)) =
+        (maybe_session.as_deref(), maybe_session_status)
+    else {
+        diagnostics.window_rollbacks = 0;
+        return;
+    };
+
+    diagnostics.prediction_depth = (session.current_frame() - session.confirmed_frame()).max(0);
+
+    let now = time.elapsed_seconds();
+    if now - diagnostics.window_started_at >= 1.0 {
+        // a rematch starts counting from 0 again
+        let rollbacks = session_status.rollbacks;
+        diagnostics.rollbacks_per_sec = rollbacks
+            .checked_sub(diagnostics.window_rollbacks)
+            .unwrap_or(rollbacks);
+        diagnostics.window_rollbacks = rollbacks;
+        diagnostics.window_started_at = now;
+    }
+}
@@ -1,111 +1,744 @@
-use bevy::{prelude::*, window::PrimaryWindow};
-use bevy_ggrs::ggrs::{self, PlayerType};
-use bevy_matchbox::prelude::SingleChannel;
-use bevy_matchbox::MatchboxSocket;
+use bevy::prelude::*;
+use bevy_ggrs::{
+    ggrs::{self, GGRSEvent, PlayerType},
+    Session,
+};
+use bevy_matchbox::prelude::{
+    ChannelConfig, MultipleChannels, PeerId, PeerState, WebRtcSocketBuilder,
+};
+use bevy_matchbox::{matchbox_socket::MessageLoopFuture, MatchboxSocket};
 
 use crate::{
-    constants::{AppState, GgrsConfig, INPUT_LEFT, INPUT_RIGHT},
-    resources::{AppConfig, AppConfigAssets, LocalPlayerHandle},
+    components::ui::InGameResultMenuUi,
+    constants::{
+        AppState, GgrsConfig, GGRS_CHANNEL, GGRS_RESET_TICKS, LOBBY_COUNTDOWN, NICKNAME_MAX_LEN,
+        SIMULATION_FPS, SPECTATOR_CATCHUP_SPEED, SPECTATOR_MAX_FRAMES_BEHIND,
+    },
+    resources::{
+        matchmaking::{
+            MatchLobby, MatchmakingRole, MatchmakingSetting, MatchmakingStatus, Nickname,
+            PeerRoles, SocketClosed,
+        },
+        session_config::{GgrsSessionConfig, LaunchArgs},
+        session_status::{
+            MatchEnd, RematchStatus, SessionEndReason, SessionStatus, SimulationFrame,
+        },
+        AppConfig, AppConfigAssets, InGameMode, InGameSetting, LocalPlayerHandle, UiAssets,
+    },
+    systems::ui::in_game_ui_systems::{build_in_game_result_menu, result_rows},
+    utils::{
+        input_utils::{LocalInput, PlayerInput},
+        network_simulator::SimulatedSocket,
+        network_utils::{
+            receive_messages, send_message, GgrsChannel, LanSocket, NetAddress, NetMessage,
+            NetMessenger,
+        },
+    },
 };
 
+pub fn init_ggrs_session_config(
+    mut commands: Commands,
+    app_config_assets: Res<AppConfigAssets>,
+    assets: Res<Assets<AppConfig>>,
+    launch_args: Res<LaunchArgs>,
+) {
+    let mut session_config = match assets.get(&app_config_assets.main) {
+        Some(config) => config.ggrs,
+        None => GgrsSessionConfig::default(),
+    };
+    session_config.override_with_args(&launch_args.0);
+    let session_config = session_config.validated();
+    info!("ggrs session config: {:?}", session_config);
+    commands.insert_resource(session_config);
+}
+
+// e.g. `cargo run -- --nickname Alice`, a random one otherwise
+pub fn init_nickname(mut commands: Commands, launch_args: Res<LaunchArgs>) {
+    let nickname = launch_args
+        .0
+        .windows(2)
+        .find(|pair| pair[0] == "--nickname")
+        .and_then(|pair| Nickname::new(&pair[1]))
+        .unwrap_or_else(|| Nickname::generate(&fastrand::Rng::new()));
+    info!("nickname: {}", nickname.0);
+    commands.insert_resource(nickname);
+}
+
 pub fn start_matchbox_socket(
     mut commands: Commands,
+    time: Res<Time>,
+    matchmaking_setting: Res<MatchmakingSetting>,
     app_config_assets: Res<AppConfigAssets>,
     assets: Res<Assets<AppConfig>>,
 ) {
-    let base_url = {
-        if let Some(config) = assets.get(&app_config_assets.main) {
-            config.signaling_server_addr.clone()
-        } else {
-            "ws://127.0.0.1:3536".to_string()
-        }
+    let status = open_matchbox_socket(
+        &mut commands,
+        &matchmaking_setting,
+        assets.get(&app_config_assets.main),
+        time.elapsed_seconds(),
+    );
+    commands.insert_resource(status);
+}
+
+pub fn open_matchbox_socket(
+    commands: &mut Commands,
+    matchmaking_setting: &MatchmakingSetting,
+    maybe_config: Option<&AppConfig>,
+    now: f32,
+) -> MatchmakingStatus {
+    commands.insert_resource(PeerRoles::default());
+    commands.insert_resource(MatchLobby::default());
+    let Some(config) = maybe_config else {
+        error!("matchmaking failed: app config is not loaded, no signaling server address");
+        return MatchmakingStatus {
+            error: Some("Missing app config".to_string()),
+            ..default()
+        };
     };
-    let room_url = format!("{}/quick_match?next=2", base_url);
+    let room_url = matchmaking_setting.room_url(&config.signaling_server_addr);
 
     info!("connecting to matchbox server: {:?}", room_url);
-    commands.insert_resource(MatchboxSocket::new_ggrs(room_url));
+    let (socket, message_loop) = WebRtcSocketBuilder::new(room_url.clone())
+        .add_channel(ChannelConfig::ggrs())
+        .add_channel(ChannelConfig::reliable())
+        .build();
+    let socket_closed = SocketClosed::default();
+    let on_closed = socket_closed.clone();
+    let message_loop: MessageLoopFuture = Box::pin(async move {
+        let result = message_loop.await;
+        on_closed.set(match &result {
+            Ok(()) => "the signaling server closed the connection".to_string(),
+            Err(err) => err.to_string(),
+        });
+        result
+    });
+    commands.insert_resource(MatchboxSocket::from((socket, message_loop)));
+    MatchmakingStatus {
+        room_url,
+        socket_closed,
+        started_at: now,
+        connect_timeout: config.signaling_connect_timeout,
+        match_timeout: config.matchmaking_timeout,
+        ..default()
+    }
+}
+
+fn fail_matchmaking(
+    commands: &mut Commands,
+    status: &mut MatchmakingStatus,
+    error: &str,
+    cause: String,
+) {
+    error!("matchmaking failed: {}", cause);
+    status.error = Some(error.to_string());
+    commands.remove_resource::<MatchboxSocket<MultipleChannels>>();
+}
+
+pub fn close_ggrs_session(mut commands: Commands) {
+    commands.remove_resource::<Session<GgrsConfig>>();
 }
 
 pub fn close_matchbox_socket(mut commands: Commands) {
-    commands.remove_resource::<MatchboxSocket<SingleChannel>>();
+    commands.remove_resource::<MatchboxSocket<MultipleChannels>>();
+    commands.remove_resource::<PeerRoles>();
+    commands.remove_resource::<MatchLobby>();
+    commands.remove_resource::<MatchmakingStatus>();
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn wait_for_players(
     mut commands: Commands,
-    mut socket: ResMut<MatchboxSocket<SingleChannel>>,
+    time: Res<Time>,
+    in_game_setting: Res<InGameSetting>,
+    session_config: Res<GgrsSessionConfig>,
+    matchmaking_setting: Res<MatchmakingSetting>,
+    nickname: Res<Nickname>,
+    mut status: ResMut<MatchmakingStatus>,
+    mut peer_roles: ResMut<PeerRoles>,
+    mut lobby: ResMut<MatchLobby>,
+    maybe_socket: Option<ResMut<MatchboxSocket<MultipleChannels>>>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    if socket.get_channel(0).is_err() {
+    let Some(mut socket) = maybe_socket else {
+        return; // failed, waiting for a retry
+    };
+    if status.error.is_some() || socket.get_channel(GGRS_CHANNEL).is_err() {
         return; // we've already started
     }
+    if let Some(cause) = status.socket_closed.cause() {
+        fail_matchmaking(&mut commands, &mut status, "Lost the server", cause);
+        return;
+    }
+    let now = time.elapsed_seconds();
+    let elapsed = now - status.started_at;
 
-    // Check for new connections
-    socket.update_peers();
-    let players = socket.players();
+    // Check for new connections, every peer tells the others who it is and if it plays or watches
+    for (peer, state) in socket.update_peers() {
+        match state {
+            PeerState::Connected => {
+                send_message(
+                    &mut socket,
+                    peer,
+                    &NetMessage::Role(matchmaking_setting.role),
+                );
+                send_message(&mut socket, peer, &NetMessage::Nickname(nickname.0.clone()));
+                if lobby.sent_ready {
+                    send_message(&mut socket, peer, &NetMessage::Ready(true));
+                }
+            }
+            PeerState::Disconnected => {
+                peer_roles.remove(&peer);
+                lobby.remove_peer(peer);
+            }
+        }
+    }
+    for (peer, message) in receive_messages(&mut socket) {
+        match message {
+            NetMessage::Role(role) => {
+                peer_roles.insert(peer, role);
+            }
+            NetMessage::Nickname(name) => {
+                let name: String = name.chars().take(NICKNAME_MAX_LEN).collect();
+                lobby.nicknames.insert(peer, name);
+            }
+            NetMessage::Ready(ready) => {
+                if ready {
+                    lobby.ready_peers.insert(peer);
+                } else {
+                    lobby.ready_peers.remove(&peer);
+                }
+            }
+            NetMessage::Countdown(started) => {
+                lobby.countdown_started_at = started.then_some(now);
+            }
+            NetMessage::Start { players } => {
+                lobby.host_start = Some(players);
+            }
+            NetMessage::MatchStarted => {
+                let cause = format!("{:?} is already playing without us", peer);
+                fail_matchmaking(&mut commands, &mut status, "Match already started", cause);
+                return;
+            }
+            _ => {}
+        }
+    }
+    if lobby.local_ready != lobby.sent_ready {
+        lobby.sent_ready = lobby.local_ready;
+        broadcast_message(
+            &mut socket,
+            &peer_roles,
+            &NetMessage::Ready(lobby.local_ready),
+        );
+    }
 
-    let num_players = 2;
-    if players.len() < num_players {
-        return; // wait for more players
+    let Some(local_id) = socket.id() else {
+        // not connected to the signaling server yet
+        if elapsed > status.connect_timeout {
+            let cause = format!(
+                "no answer from {} after {}s",
+                status.room_url, status.connect_timeout
+            );
+            fail_matchmaking(&mut commands, &mut status, "Can't reach the server", cause);
+        }
+        return;
+    };
+    if !status.connected {
+        info!("connected to matchbox server as {:?}", local_id);
+        status.connected = true;
+        lobby.local_id = Some(local_id);
+        lobby.nicknames.insert(local_id, nickname.0.clone());
     }
 
-    info!("All peers have joined, going in-game");
+    // the host decides when the match starts and who plays, everyone else follows it,
+    // ggrs can't add a spectator to a running session so spectators have to be listed too
+    if let Some(player_peers) = lobby.host_start.take() {
+        if matchmaking_setting.role == MatchmakingRole::Player && !player_peers.contains(&local_id)
+        {
+            let cause = "the host started with other players".to_string();
+            fail_matchmaking(&mut commands, &mut status, "Match already started", cause);
+            return;
+        }
+        info!("the host started the match, going in-game");
+        start_online_match(
+            &mut commands,
+            &mut socket,
+            &in_game_setting,
+            &session_config,
+            &peer_roles,
+            local_id,
+            &player_peers,
+        );
+        next_state.set(AppState::InGame);
+        return;
+    }
 
-    // create a GGRS P2P session
-    let mut session_builder = ggrs::SessionBuilder::<GgrsConfig>::new()
-        .with_num_players(num_players)
-        .with_input_delay(2);
+    if matchmaking_setting.role == MatchmakingRole::Player
+        && is_room_full(&peer_roles, local_id, in_game_setting.num_players)
+    {
+        let cause = format!(
+            "{} players with a lower peer id are already in the room",
+            in_game_setting.num_players
+        );
+        fail_matchmaking(&mut commands, &mut status, "Room is full", cause);
+        return;
+    }
 
-    for (i, player) in players.into_iter().enumerate() {
-        if player == PlayerType::Local {
-            commands.insert_resource(LocalPlayerHandle(i));
+    let Some(player_peers) = select_player_peers(
+        &peer_roles,
+        matchmaking_setting.role,
+        local_id,
+        in_game_setting.num_players,
+    ) else {
+        // wait for more players
+        if !lobby.players.is_empty() {
+            info!("a player left the lobby");
+            lobby.players.clear();
+            lobby.countdown_started_at = None;
+        }
+        if status.match_timeout > 0.0 && elapsed > status.match_timeout {
+            let cause = format!(
+                "only {} of {} players after {}s",
+                peer_roles.peers_with_role(MatchmakingRole::Player).len() + 1,
+                in_game_setting.num_players,
+                status.match_timeout
+            );
+            fail_matchmaking(&mut commands, &mut status, "No match found", cause);
+        }
+        return;
+    };
+    if lobby.players != player_peers {
+        lobby.players = player_peers.clone();
+        lobby.countdown_started_at = None;
+    }
+
+    if player_peers[0] != local_id {
+        return; // the host tells us when it starts
+    }
+
+    // only the host counts down, once everyone is ready, and anyone getting unready stops it
+    match lobby.countdown_started_at {
+        None if lobby.all_ready() => {
+            info!("all players are ready, starting in {}s", LOBBY_COUNTDOWN);
+            lobby.countdown_started_at = Some(now);
+            broadcast_message(&mut socket, &peer_roles, &NetMessage::Countdown(true));
+            return;
+        }
+        Some(_) if !lobby.all_ready() => {
+            info!("a player is not ready anymore, countdown cancelled");
+            lobby.countdown_started_at = None;
+            broadcast_message(&mut socket, &peer_roles, &NetMessage::Countdown(false));
+            return;
         }
+        Some(started_at) if now - started_at >= LOBBY_COUNTDOWN => {}
+        _ => return,
+    }
 
-        session_builder = session_builder
-            .add_player(player, i)
-            .expect("failed to add player");
+    info!("All peers are ready, going in-game");
+    start_online_match(
+        &mut commands,
+        &mut socket,
+        &in_game_setting,
+        &session_config,
+        &peer_roles,
+        local_id,
+        &player_peers,
+    );
+    next_state.set(AppState::InGame);
+}
+
+fn start_online_match(
+    commands: &mut Commands,
+    socket: &mut MatchboxSocket<MultipleChannels>,
+    in_game_setting: &InGameSetting,
+    session_config: &GgrsSessionConfig,
+    peer_roles: &PeerRoles,
+    local_id: PeerId,
+    player_peers: &[PeerId],
+) {
+    // the player with the lowest peer id is the host, it starts the other players and
+    // the spectators it hosts, anyone else it can see is turned away instead of waiting
+    // for a session it isn't part of
+    let mut spectators = Vec::new();
+    if player_peers[0] == local_id {
+        let spectator_peers = peer_roles.peers_with_role(MatchmakingRole::Spectator);
+        let start = NetMessage::Start {
+            players: player_peers.to_vec(),
+        };
+        let connected_peers: Vec<PeerId> = socket.connected_peers().collect();
+        for peer in connected_peers {
+            if player_peers.contains(&peer) {
+                send_message(socket, peer, &start);
+            } else if spectator_peers.contains(&peer) {
+                send_message(socket, peer, &start);
+                spectators.push(NetAddress::Peer(peer));
+            } else {
+                send_message(socket, peer, &NetMessage::MatchStarted);
+            }
+        }
     }
 
     // move the channel out of the socket (required because GGRS takes ownership of it)
-    let socket = socket.take_channel(0).unwrap();
+    let channel = GgrsChannel::new(socket.take_channel(GGRS_CHANNEL).unwrap());
+    commands.insert_resource(channel.clone());
 
-    // start the GGRS session
-    let ggrs_session = session_builder
-        .start_p2p_session(socket)
-        .expect("failed to start session");
+    let players: Vec<Option<NetAddress>> = player_peers
+        .iter()
+        .map(|peer| (*peer != local_id).then_some(NetAddress::Peer(*peer)))
+        .collect();
+    start_ggrs_session(
+        commands,
+        in_game_setting,
+        session_config,
+        &players,
+        &spectators,
+        channel,
+    );
+}
 
-    commands.insert_resource(bevy_ggrs::Session::P2PSession(ggrs_session));
-    next_state.set(AppState::InGame);
+fn broadcast_message(
+    socket: &mut MatchboxSocket<MultipleChannels>,
+    peer_roles: &PeerRoles,
+    message: &NetMessage,
+) {
+    for peer in peer_roles.keys() {
+        send_message(socket, *peer, message);
+    }
 }
 
-pub fn network_input_system(
-    _: In<ggrs::PlayerHandle>,
-    keys: Res<Input<KeyCode>>,
-    touches: Res<Touches>,
-    primary_query: Query<&Window, With<PrimaryWindow>>,
-) -> u8 {
-    let mut input = 0u8;
+// players are picked by peer id, a player with enough lower ids ahead can't get in
+fn is_room_full(peer_roles: &PeerRoles, local_id: PeerId, num_players: usize) -> bool {
+    peer_roles
+        .peers_with_role(MatchmakingRole::Player)
+        .iter()
+        .filter(|x| **x < local_id)
+        .count()
+        >= num_players
+}
 
-    if keys.any_pressed([KeyCode::Left, KeyCode::A]) {
-        input |= INPUT_LEFT
+fn select_player_peers(
+    peer_roles: &PeerRoles,
+    role: MatchmakingRole,
+    local_id: PeerId,
+    num_players: usize,
+) -> Option<Vec<PeerId>> {
+    let mut player_peers = peer_roles.peers_with_role(MatchmakingRole::Player);
+    if role == MatchmakingRole::Player {
+        player_peers.push(local_id);
+        player_peers.sort();
     }
-    if keys.any_pressed([KeyCode::Right, KeyCode::D]) {
-        input |= INPUT_RIGHT;
+    if player_peers.len() < num_players {
+        return None;
     }
+    player_peers.truncate(num_players);
+    Some(player_peers)
+}
+
+// `players` is indexed by player handle, `None` is the local player
+pub fn start_ggrs_session(
+    commands: &mut Commands,
+    in_game_setting: &InGameSetting,
+    session_config: &GgrsSessionConfig,
+    players: &[Option<NetAddress>],
+    spectators: &[NetAddress],
+    channel: GgrsChannel,
+) {
+    let num_players = in_game_setting.num_players;
+
+    // create a GGRS session
+    let session_builder = ggrs::SessionBuilder::<GgrsConfig>::new()
+        .with_num_players(num_players)
+        .with_input_delay(session_config.input_delay)
+        .with_max_prediction_window(session_config.max_prediction)
+        .with_desync_detection_mode(session_config.desync_detection())
+        .with_disconnect_timeout(session_config.disconnect_timeout())
+        .with_disconnect_notify_delay(session_config.disconnect_notify_start())
+        .with_fps(SIMULATION_FPS)
+        .expect("invalid fps");
 
-    let maybe_touch = touches.iter().last();
-    if let Ok(window) = primary_query.get_single() {
-        let center = window.width() / 2.0;
-        if let Some(touch) = maybe_touch {
-            if touches.get_pressed(touch.id()).is_some() {
-                let tx = touch.position().x;
-                if tx > center {
-                    input |= INPUT_RIGHT;
-                } else if tx < center {
-                    input |= INPUT_LEFT;
+    let socket = SimulatedSocket::new(channel, session_config.network_simulator);
+    if session_config.network_simulator.enabled {
+        warn!(
+            "simulating network conditions: {:?}",
+            session_config.network_simulator
+        );
+    }
+
+    let session = if in_game_setting.is_spectator {
+        commands.remove_resource::<LocalPlayerHandle>();
+        let session_builder = session_builder
+            .with_max_frames_behind(SPECTATOR_MAX_FRAMES_BEHIND)
+            .expect("invalid max frames behind")
+            .with_catchup_speed(SPECTATOR_CATCHUP_SPEED)
+            .expect("invalid catchup speed");
+
+        let host = players[0].expect("spectators have no local player");
+        info!("watching match hosted by {:?}", host);
+        Session::SpectatorSession(session_builder.start_spectator_session(host, socket))
+    } else {
+        let mut session_builder = session_builder;
+        for (handle, addr) in players.iter().enumerate() {
+            let player = match addr {
+                Some(addr) => PlayerType::Remote(*addr),
+                None => {
+                    commands.insert_resource(LocalPlayerHandle(handle));
+                    PlayerType::Local
                 }
+            };
+            session_builder = session_builder
+                .add_player(player, handle)
+                .expect("failed to add player");
+        }
+        for (i, addr) in spectators.iter().enumerate() {
+            session_builder = session_builder
+                .add_player(PlayerType::Spectator(*addr), num_players + i)
+                .expect("failed to add spectator");
+        }
+
+        // start the GGRS session
+        let ggrs_session = session_builder
+            .start_p2p_session(socket)
+            .expect("failed to start session");
+        Session::P2PSession(ggrs_session)
+    };
+
+    commands.insert_resource(session);
+    commands.insert_resource(SimulationFrame::default());
+    commands.insert_resource(MatchEnd::default());
+    commands.insert_resource(SessionStatus {
+        players: players.to_vec(),
+        ..default()
+    });
+    commands.insert_resource(RematchStatus::default());
+}
+
+pub fn receive_rematch_requests_system(
+    mut in_game_setting: ResMut<InGameSetting>,
+    mut net_messenger: NetMessenger,
+    maybe_session_status: Option<ResMut<SessionStatus>>,
+    maybe_rematch_status: Option<ResMut<RematchStatus>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let (Some(mut session_status), Some(mut rematch_status)) =
+        (maybe_session_status, maybe_rematch_status)
+    else {
+        return;
+    };
+
+    for (peer, message) in net_messenger.receive() {
+        match message {
+            NetMessage::Rematch { seed } => {
+                info!("{:?} wants a rematch", peer);
+                rematch_status.remote_seeds.insert(peer, seed);
+            }
+            NetMessage::LanCancel if session_status.players.first() == Some(&Some(peer)) => {
+                session_status.cancelled = true;
+            }
+            _ => {}
+        }
+    }
+
+    if !session_status.can_rematch() {
+        return;
+    }
+    if let Some(seed) = rematch_status.agreed_seed(&session_status.remote_players()) {
+        info!("all players agreed to a rematch, seed: {}", seed);
+        in_game_setting.seed = seed;
+        next_state.set(AppState::Rematch);
+    }
+}
+
+pub fn request_rematch(
+    net_messenger: &mut NetMessenger,
+    session_status: &SessionStatus,
+    rematch_status: &mut RematchStatus,
+) {
+    if rematch_status.local_seed.is_some() || !session_status.can_rematch() {
+        return;
+    }
+    let seed = fastrand::Rng::new().u64(..);
+    rematch_status.local_seed = Some(seed);
+    for peer in session_status.remote_players() {
+        net_messenger.send(&peer, &NetMessage::Rematch { seed });
+    }
+}
+
+// bevy_ggrs only resets its frame count on a tick without a session, so the old session is
+// closed when leaving the game and the new one waits a couple of ticks
+#[allow(clippy::too_many_arguments)]
+pub fn start_rematch_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    in_game_setting: Res<InGameSetting>,
+    session_config: Res<GgrsSessionConfig>,
+    maybe_session_status: Option<Res<SessionStatus>>,
+    maybe_channel: Option<Res<GgrsChannel>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut entered_at: Local<Option<f32>>,
+) {
+    if in_game_setting.mode == InGameMode::Online {
+        let now = time.elapsed_seconds();
+        let waited = now - *entered_at.get_or_insert(now);
+        if waited < GGRS_RESET_TICKS as f32 / SIMULATION_FPS as f32 {
+            return;
+        }
+        *entered_at = None;
+
+        let (Some(session_status), Some(channel)) = (maybe_session_status, maybe_channel) else {
+            error!("online rematch without an open socket");
+            next_state.set(AppState::MainMenu);
+            return;
+        };
+
+        // spectators are not part of the rematch handshake, they have to join again
+        start_ggrs_session(
+            &mut commands,
+            &in_game_setting,
+            &session_config,
+            &session_status.players,
+            &[],
+            channel.clone(),
+        );
+    }
+    next_state.set(AppState::InGame);
+}
+
+// a peer connecting to the room during the match can't join it anymore, tell it so it
+// doesn't wait in the lobby forever
+pub fn refuse_late_peers_system(
+    in_game_setting: Res<InGameSetting>,
+    maybe_socket: Option<ResMut<MatchboxSocket<MultipleChannels>>>,
+) {
+    let Some(mut socket) = maybe_socket else {
+        return;
+    };
+    if in_game_setting.is_spectator {
+        return;
+    }
+    for (peer, state) in socket.update_peers() {
+        if let PeerState::Connected = state {
+            info!("{:?} joined the room after the match started", peer);
+            send_message(&mut socket, peer, &NetMessage::MatchStarted);
+        }
+    }
+}
+
+pub fn handle_ggrs_events_system(
+    mut commands: Commands,
+    in_game_setting: Res<InGameSetting>,
+    ui_assets: Res<UiAssets>,
+    maybe_session: Option<ResMut<Session<GgrsConfig>>>,
+    maybe_session_status: Option<ResMut<SessionStatus>>,
+    menu_query: Query<Entity, With<InGameResultMenuUi>>,
+) {
+    let (Some(mut session), Some(mut session_status)) = (maybe_session, maybe_session_status)
+    else {
+        return;
+    };
+
+    let events: Vec<GGRSEvent<GgrsConfig>> = match session.as_mut() {
+        Session::P2PSession(s) => s.events().collect(),
+        Session::SpectatorSession(s) => s.events().collect(),
+        Session::SyncTestSession(_) => Vec::new(),
+    };
+    for event in events {
+        match event {
+            GGRSEvent::NetworkInterrupted {
+                addr,
+                disconnect_timeout,
+            } => {
+                warn!(
+                    "connection to {:?} interrupted, disconnecting in {}ms",
+                    addr, disconnect_timeout
+                );
+                session_status.interrupted_peers.insert(addr);
+            }
+            GGRSEvent::NetworkResumed { addr } => {
+                info!("connection to {:?} resumed", addr);
+                session_status.interrupted_peers.remove(&addr);
             }
+            GGRSEvent::Disconnected { addr } => {
+                warn!("{:?} disconnected", addr);
+                session_status.interrupted_peers.remove(&addr);
+                session_status.disconnected_peers.insert(addr);
+            }
+            GGRSEvent::DesyncDetected {
+                frame,
+                local_checksum,
+                remote_checksum,
+                addr,
+            } => {
+                error!(
+                    "desync with {:?} at frame {}, local checksum {:x}, remote checksum {:x}",
+                    addr, frame, local_checksum, remote_checksum
+                );
+            }
+            _ => {}
         }
     }
 
-    input
+    if session_status.ended.is_some() {
+        return;
+    }
+    let ended = if session_status.cancelled {
+        Some(SessionEndReason::Cancelled)
+    } else if in_game_setting.is_spectator {
+        // a spectator is only connected to the host
+        if session_status.disconnected_peers.is_empty() {
+            None
+        } else {
+            Some(SessionEndReason::HostLeft)
+        }
+    } else {
+        // the host also sees its spectators disconnect
+        let disconnected_players = session_status
+            .remote_players()
+            .iter()
+            .filter(|x| session_status.disconnected_peers.contains(*x))
+            .count();
+        if disconnected_players + 1 >= in_game_setting.num_players {
+            Some(SessionEndReason::PlayersLeft)
+        } else {
+            None
+        }
+    };
+
+    if let Some(reason) = ended {
+        info!("ending online session: {:?}", reason);
+        session_status.ended = Some(reason);
+        commands.remove_resource::<Session<GgrsConfig>>();
+
+        if menu_query.is_empty() {
+            let message = match reason {
+                SessionEndReason::PlayersLeft if in_game_setting.num_players == 2 => {
+                    "Opponent left, you win!"
+                }
+                SessionEndReason::PlayersLeft => "Everyone left, you win!",
+                SessionEndReason::HostLeft => "Host left the match",
+                SessionEndReason::Cancelled => "A player didn't answer, the game was called off",
+            };
+            build_in_game_result_menu(
+                &mut commands,
+                &ui_assets,
+                result_rows(in_game_setting.num_players),
+                Some(message),
+            );
+        }
+    }
+}
+
+pub fn close_online_session(mut commands: Commands) {
+    commands.remove_resource::<Session<GgrsConfig>>();
+    commands.remove_resource::<SessionStatus>();
+    commands.remove_resource::<RematchStatus>();
+    commands.remove_resource::<GgrsChannel>();
+    commands.remove_resource::<LanSocket>();
+    commands.remove_resource::<LocalPlayerHandle>();
+    commands.remove_resource::<MatchboxSocket<MultipleChannels>>();
+    commands.remove_resource::<PeerRoles>();
+    commands.remove_resource::<MatchmakingStatus>();
+}
+
+pub fn network_input_system(_: In<ggrs::PlayerHandle>, local_input: LocalInput) -> PlayerInput {
+    // there is a single local player online
+    local_input.read(0)
 }
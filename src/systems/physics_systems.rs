@@ -7,6 +7,7 @@ use crate::{
     components::{
         conveyor_brick::ConveyorBrick,
         fake_brick::FakeBrick,
+        garbage::Garbage,
         normal_brick::NormalBrick,
         physics::{BoxCollider, LastCollisions, Velocity},
         player::{Jumping, Player},
@@ -34,6 +35,7 @@ pub fn player_collision_system(
             &mut Velocity,
             &mut LastCollisions,
             Option<&Jumping>,
+            &Player,
         ),
         With<Player>,
     >,
@@ -47,6 +49,7 @@ pub fn player_collision_system(
             Option<&FakeBrick>,
             Option<&SpringBrick>,
             Option<&ConveyorBrick>,
+            Option<&Garbage>,
         ),
         Without<Player>,
     >,
@@ -63,6 +66,7 @@ pub fn player_collision_system(
         mut player_velocity,
         mut player_last_collisions,
         maybe_player_jumping,
+        player,
     ) in player_query.iter_mut()
     {
         let is_player_jumping = match maybe_player_jumping {
@@ -96,8 +100,13 @@ pub fn player_collision_system(
             maybe_fake_brick,
             maybe_spring_brick,
             maybe_conveyor_brick,
+            maybe_garbage,
         ) in collider_query.iter()
         {
+            // someone else's garbage
+            if maybe_garbage.map_or(false, |x| x.target != player.handle) {
+                continue;
+            }
             let collider_translation = get_collider_translation(transform, collider);
             let collider_size = get_collider_size(transform, collider);
 
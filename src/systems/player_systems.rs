@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 
 use crate::{
@@ -13,7 +13,10 @@ use crate::{
     },
     constants::PHYSICS_DELTA,
     events::player_events::PlayerEnterDeadEvent,
-    resources::PlayerAssets,
+    resources::{
+        session_status::{MatchEnd, SimulationFrame},
+        InGameSetting, PlayerAssets, StageSize,
+    },
 };
 
 pub fn player_controller_system(
@@ -24,7 +27,7 @@ pub fn player_controller_system(
             Option<&Flying>,
             Option<&ConveyorMoved>,
         ),
-        With<Player>,
+        (With<Player>, Without<Downed>),
     >,
 ) {
     for (mut velocity, userinput, maybe_flying, maybe_conveyor_moved) in player_query.iter_mut() {
@@ -220,18 +223,67 @@ pub fn jumping_timer_system(
 }
 
 pub fn player_out_window_die_system(
-    primary_query: Query<&Window, With<PrimaryWindow>>,
-    mut player_query: Query<(&mut Health, &Transform, &BoxCollider), (Without<Dead>, With<Player>)>,
+    in_game_setting: Res<InGameSetting>,
+    stage_size: Res<StageSize>,
+    mut player_query: Query<
+        (&Player, &mut Health, &Transform, &BoxCollider),
+        (Without<Dead>, With<Player>),
+    >,
 ) {
-    if let Ok(window) = primary_query.get_single() {
-        let height = window.height().trunc();
-        let width = window.width();
-        for (mut health, transform, collider) in player_query.iter_mut() {
-            let x = transform.translation.x + collider.size.x / 2.0;
-            let y = transform.translation.y + collider.size.y / 2.0;
-            if x < -width / 2.0 || x > width / 2.0 || y < -height / 2.0 || y > height / 2.0 {
-                health.value = health.clamp(0);
-            }
+    for (player, mut health, transform, collider) in player_query.iter_mut() {
+        let shaft = in_game_setting.player_shaft(player.handle);
+        if is_out_of_stage(&stage_size, shaft, transform, collider) {
+            health.value = health.clamp(0);
+        }
+    }
+}
+
+pub fn is_out_of_stage(
+    stage_size: &StageSize,
+    shaft: usize,
+    transform: &Transform,
+    collider: &BoxCollider,
+) -> bool {
+    let height = stage_size.height.trunc();
+    let width = stage_size.width;
+    let x = transform.translation.x - stage_size.shaft_x(shaft) + collider.size.x / 2.0;
+    let y = transform.translation.y + collider.size.y / 2.0;
+    x < -width / 2.0 || x > width / 2.0 || y < -height / 2.0 || y > height / 2.0
+}
+
+// a player stops counting once nobody else was alive on the last tick, so the last
+// survivor ends one tick ahead and the match is decided
+pub fn survival_ticks_system(mut player_query: Query<(&Health, &mut SurvivalTicks), With<Player>>) {
+    let last_ticks: Vec<u32> = player_query.iter().map(|(_, ticks)| ticks.0).collect();
+    for (health, mut ticks) in player_query.iter_mut() {
+        if health.value <= 0 {
+            continue;
         }
+        let others = last_ticks.len() - 1;
+        let others_alive = last_ticks.iter().filter(|x| **x >= ticks.0).count() - 1;
+        if others == 0 || others_alive > 0 {
+            ticks.0 += 1;
+        }
+    }
+}
+
+// online, versus is over when one player is left and co-op when the whole team is down
+pub fn match_end_system(
+    in_game_setting: Res<InGameSetting>,
+    frame: Res<SimulationFrame>,
+    mut match_end: ResMut<MatchEnd>,
+    player_query: Query<&Health, With<Player>>,
+) {
+    if match_end.frame.is_some() || player_query.is_empty() {
+        return;
+    }
+    let alive = player_query.iter().filter(|x| x.value > 0).count();
+    let ended = if in_game_setting.is_coop() {
+        alive == 0
+    } else {
+        alive <= 1
+    };
+    if ended {
+        match_end.frame = Some(frame.0);
     }
 }
@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::player::{Health, Player, PlayerScore},
+    constants::{AppState, BEST_REPLAY_PATH, BRICK_ROW_SPACING, LAST_REPLAY_PATH},
+    resources::{
+        replay::{Replay, ReplayPlayback, ReplayRecorder},
+        session_config::LaunchArgs,
+        InGameMode, InGameSetting, StageSize,
+    },
+};
+
+// e.g. `cargo run -- --replay last_run.nsreplay`, plays once on the first visit to the main menu
+pub fn init_replay_playback(
+    mut commands: Commands,
+    launch_args: Res<LaunchArgs>,
+    mut in_game_setting: ResMut<InGameSetting>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut played: Local<bool>,
+) {
+    if *played {
+        return;
+    }
+    *played = true;
+    let Some(path) = launch_args
+        .0
+        .windows(2)
+        .find(|pair| pair[0] == "--replay")
+        .map(|pair| pair[1].clone())
+    else {
+        return;
+    };
+    match Replay::load(&path) {
+        Ok(replay) => {
+            info!(
+                "playing replay {}, {} ticks, score {}",
+                path,
+                replay.ticks(),
+                replay.score
+            );
+            in_game_setting.rules = replay.rules;
+            in_game_setting.set_offline_local(replay.num_players);
+            in_game_setting.seed = replay.seed;
+            commands.insert_resource(ReplayPlayback::new(replay));
+            next_state.set(AppState::InGame);
+        }
+        Err(err) => error!("failed to load replay {}: {}", path, err),
+    }
+}
+
+pub fn close_replay_playback(mut commands: Commands) {
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+pub fn start_replay_recording(
+    mut commands: Commands,
+    in_game_setting: Res<InGameSetting>,
+    stage_size: Res<StageSize>,
+    maybe_playback: Option<ResMut<ReplayPlayback>>,
+) {
+    if in_game_setting.mode != InGameMode::Offline {
+        commands.remove_resource::<ReplayRecorder>();
+        return;
+    }
+    if let Some(mut playback) = maybe_playback {
+        playback.tick = 0;
+    }
+    commands.insert_resource(ReplayRecorder {
+        replay: Replay {
+            seed: in_game_setting.seed,
+            num_players: in_game_setting.num_players,
+            stage_width: stage_size.width.trunc() as u16,
+            stage_height: stage_size.height.trunc() as u16,
+            rules: in_game_setting.rules,
+            ..default()
+        },
+        finished: false,
+    });
+}
+
+pub fn record_replay_track_system(
+    maybe_recorder: Option<ResMut<ReplayRecorder>>,
+    player_query: Query<(&Player, &Transform)>,
+) {
+    let Some(mut recorder) = maybe_recorder else {
+        return;
+    };
+    if let Some((_, transform)) = player_query.iter().find(|(player, _)| player.handle == 0) {
+        recorder.push_position(transform.translation.truncate());
+    }
+}
+
+// the run ends with the last player, a live run is saved and a replay is checked
+// against what it recorded
+pub fn finish_replay_recording_system(
+    maybe_recorder: Option<ResMut<ReplayRecorder>>,
+    maybe_playback: Option<Res<ReplayPlayback>>,
+    player_query: Query<(&Health, &PlayerScore), With<Player>>,
+) {
+    let Some(mut recorder) = maybe_recorder else {
+        return;
+    };
+    if recorder.finished || player_query.iter().any(|(health, _)| health.value > 0) {
+        return;
+    }
+    recorder.finished = true;
+    let replay = &mut recorder.replay;
+    replay.score = player_query
+        .iter()
+        .map(|(_, player_score)| player_score.score)
+        .max()
+        .unwrap_or(0);
+    replay.depth = replay.ticks() as u32 / BRICK_ROW_SPACING as u32;
+
+    match maybe_playback {
+        Some(playback) if playback.replay.same_result(replay) => {
+            info!(
+                "replay matched, score {} depth {}",
+                replay.score, replay.depth
+            );
+        }
+        Some(playback) => {
+            warn!(
+                "replay diverged, recorded score {} depth {}, got score {} depth {}",
+                playback.replay.score, playback.replay.depth, replay.score, replay.depth
+            );
+        }
+        None => {
+            save_replay(replay, LAST_REPLAY_PATH);
+            // only single player runs can be raced
+            let is_best = replay.num_players == 1
+                && match Replay::load(BEST_REPLAY_PATH) {
+                    Ok(best) => replay.is_better_than(&best),
+                    Err(_) => true,
+                };
+            if is_best {
+                save_replay(replay, BEST_REPLAY_PATH);
+            }
+        }
+    }
+}
+
+fn save_replay(replay: &Replay, path: &str) {
+    match replay.save(path) {
+        Ok(_) => info!("saved replay to {}", path),
+        Err(err) => warn!("failed to save replay to {}: {}", path, err),
+    }
+}
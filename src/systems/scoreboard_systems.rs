@@ -1,17 +1,56 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
 use crate::{
     components::player::{Dead, Player, PlayerScore},
-    resources::scoreboard::{ScoreTimer, Scoreboard},
+    constants::PHYSICS_DELTA,
+    resources::{
+        scoreboard::{ScoreTimer, Scoreboard},
+        session_status::MatchEnd,
+    },
 };
 
+// online the score is simulated and rolled back like the rest, every peer ends with the
+// same scores. once the match is decided it stops there
 pub fn add_score(
-    time: Res<Time>,
+    match_end: Res<MatchEnd>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut score_timer: ResMut<ScoreTimer>,
+    player_query: Query<&mut PlayerScore, (With<Player>, Without<Dead>)>,
+) {
+    if match_end.frame.is_some() {
+        return;
+    }
+    tick_score(
+        Duration::from_secs_f64(PHYSICS_DELTA),
+        &mut scoreboard,
+        &mut score_timer,
+        player_query,
+    );
+}
+
+// offline the score follows the simulated ticks so a replay ends with the same score
+pub fn add_score_fixed(
     mut scoreboard: ResMut<Scoreboard>,
     mut score_timer: ResMut<ScoreTimer>,
+    player_query: Query<&mut PlayerScore, (With<Player>, Without<Dead>)>,
+) {
+    tick_score(
+        Duration::from_secs_f64(PHYSICS_DELTA),
+        &mut scoreboard,
+        &mut score_timer,
+        player_query,
+    );
+}
+
+fn tick_score(
+    delta: Duration,
+    scoreboard: &mut Scoreboard,
+    score_timer: &mut ScoreTimer,
     mut player_query: Query<&mut PlayerScore, (With<Player>, Without<Dead>)>,
 ) {
-    score_timer.tick(time.delta());
+    score_timer.tick(delta);
     if score_timer.finished() {
         scoreboard.score += 1;
         for mut player_score in player_query.iter_mut() {
@@ -20,6 +59,7 @@ pub fn add_score(
     }
 }
 
-pub fn init_score(mut scoreboard: ResMut<Scoreboard>) {
+pub fn init_score(mut scoreboard: ResMut<Scoreboard>, mut score_timer: ResMut<ScoreTimer>) {
     scoreboard.score = 0;
+    score_timer.reset();
 }
@@ -1,3 +1,9 @@
+pub mod controls_ui_systems;
 pub mod in_game_ui_systems;
+pub mod join_room_ui_systems;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lan_lobby_ui_systems;
 pub mod main_menu_ui_systems;
-pub mod matchmaking_ui_systems;
\ No newline at end of file
+pub mod matchmaking_ui_systems;
+pub mod settings_ui_systems;
+pub mod virtual_buttons_ui_systems;
\ No newline at end of file
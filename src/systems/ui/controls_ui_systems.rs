@@ -0,0 +1,261 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{camera::MainCamera, ui::*},
+    constants::KEY_BINDINGS_PATH,
+    resources::{
+        key_bindings::{InputAction, KeyBindings, KeyRebinding},
+        UiAssets,
+    },
+    systems::ui::main_menu_ui_systems::spawn_medium_button,
+};
+
+pub fn spawn_controls_ui_all(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    key_bindings: Res<KeyBindings>,
+) {
+    commands.spawn((Camera2dBundle::default(), MainCamera {}));
+    commands.insert_resource(KeyRebinding::default());
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    gap: Size::new(Val::Px(0.0), Val::Px(16.0)),
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::GRAY),
+                ..Default::default()
+            },
+            ControlsUi {},
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                "Controls",
+                TextStyle {
+                    font: ui_assets.bold_font.clone(),
+                    font_size: 52.0,
+                    color: Color::WHITE,
+                },
+            ));
+
+            for local_player in 0..key_bindings.players.len() {
+                builder.spawn(TextBundle::from_section(
+                    format!("Player {}", local_player + 1),
+                    TextStyle {
+                        font: ui_assets.bold_font.clone(),
+                        font_size: 32.0,
+                        color: Color::WHITE,
+                    },
+                ));
+                for action in InputAction::ALL {
+                    spawn_rebind_row(builder, &ui_assets, local_player, action);
+                }
+            }
+
+            builder.spawn(TextBundle::from_section(
+                "Click a binding then press a key, Esc cancels",
+                TextStyle {
+                    font: ui_assets.medium_font.clone(),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                },
+            ));
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::new(Val::Px(8.0), Val::Px(0.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    spawn_medium_button(builder, &ui_assets, "Back", BackMainMenuButton {});
+                    spawn_medium_button(builder, &ui_assets, "Reset", ResetKeyBindingsButton {});
+                });
+        });
+}
+
+fn spawn_rebind_row(
+    builder: &mut ChildBuilder,
+    ui_assets: &Res<UiAssets>,
+    local_player: usize,
+    action: InputAction,
+) {
+    builder
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                gap: Size::new(Val::Px(16.0), Val::Px(0.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|builder| {
+            builder.spawn(TextBundle {
+                text: Text::from_section(
+                    action.label(),
+                    TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                style: Style {
+                    size: Size::new(Val::Px(120.0), Val::Auto),
+                    ..default()
+                },
+                ..default()
+            });
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(220.0), Val::Px(40.0)),
+                            ..GLOBAL_STYLES.normal_button_medium
+                        },
+                        background_color: GLOBAL_STYLES.normal_button_color.into(),
+                        ..default()
+                    },
+                    RebindKeyButton {
+                        local_player,
+                        action,
+                    },
+                ))
+                .with_children(|builder| {
+                    builder.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: ui_assets.bold_font.clone(),
+                                font_size: 16.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        RebindKeyText {
+                            local_player,
+                            action,
+                        },
+                    ));
+                });
+        });
+}
+
+pub fn despawn_controls_ui_all(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<ControlsUi>, With<MainCamera>)>>,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<KeyRebinding>();
+}
+
+pub fn update_rebind_key_text(
+    key_bindings: Res<KeyBindings>,
+    rebinding: Res<KeyRebinding>,
+    mut text_query: Query<(&mut Text, &RebindKeyText)>,
+) {
+    if !key_bindings.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+    for (mut text, rebind_key_text) in text_query.iter_mut() {
+        let target = (rebind_key_text.local_player, rebind_key_text.action);
+        text.sections[0].value = if rebinding.target == Some(target) {
+            "Press a key...".to_string()
+        } else {
+            match key_bindings.player(target.0) {
+                Some(bindings) if !bindings.keys(target.1).is_empty() => bindings
+                    .keys(target.1)
+                    .iter()
+                    .map(|key| format!("{:?}", key))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => "-".to_string(),
+            }
+        };
+    }
+}
+
+pub fn interact_with_rebind_key_button(
+    mut rebinding: ResMut<KeyRebinding>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor, &RebindKeyButton),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, mut background_color, rebind_key_button) in button_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                rebinding.target = Some((rebind_key_button.local_player, rebind_key_button.action));
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
+pub fn capture_rebind_key_system(
+    keys: Res<Input<KeyCode>>,
+    mut rebinding: ResMut<KeyRebinding>,
+    mut key_bindings: ResMut<KeyBindings>,
+) {
+    let Some((local_player, action)) = rebinding.target else {
+        return;
+    };
+    let Some(key) = keys.get_just_pressed().next().copied() else {
+        return;
+    };
+    rebinding.target = None;
+    if key == KeyCode::Escape {
+        return;
+    }
+    let Some(bindings) = key_bindings.players.get_mut(local_player) else {
+        return;
+    };
+    info!(
+        "player {} {} bound to {:?}",
+        local_player + 1,
+        action.label(),
+        key
+    );
+    *bindings.keys_mut(action) = vec![key];
+    key_bindings.save(KEY_BINDINGS_PATH);
+}
+
+pub fn interact_with_reset_key_bindings_button(
+    mut key_bindings: ResMut<KeyBindings>,
+    mut rebinding: ResMut<KeyRebinding>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ResetKeyBindingsButton>),
+    >,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                rebinding.target = None;
+                *key_bindings = KeyBindings::default();
+                key_bindings.save(KEY_BINDINGS_PATH);
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
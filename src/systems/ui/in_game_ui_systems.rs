@@ -1,22 +1,40 @@
-use std::vec;
+use std::{cmp::Ordering, vec};
 
 use bevy::prelude::*;
+use bevy_ggrs::Session;
 
 use crate::{
     components::{
-        player::{Health, Player, PlayerScore},
+        player::{Dead, Health, Player, PlayerScore, SurvivalTicks},
         ui::*,
     },
-    constants::AppState,
+    constants::{AppState, GgrsConfig, BRICK_ROW_SPACING, IN_GAME_UI_APP_BAR_HEIGHT},
     events::player_events::PlayerEnterDeadEvent,
-    resources::{scoreboard::Scoreboard, InGameMode, InGameSetting, LocalPlayerHandle, UiAssets},
+    resources::{
+        network_diagnostics::NetworkDiagnostics,
+        replay::GhostRace,
+        scoreboard::Scoreboard,
+        session_status::{MatchEnd, RematchStatus, SessionStatus},
+        InGameMode, InGameSetting, LocalPlayerHandle, UiAssets,
+    },
+    systems::network_systems::request_rematch,
+    utils::network_utils::NetMessenger,
 };
 
 pub fn build_in_game_ui(
     commands: &mut Commands,
     ui_assets: &Res<UiAssets>,
     player_handles: Vec<usize>,
+    show_player_scores: bool,
+    split_shafts: bool,
 ) {
+    // racers each get their own score over their shaft
+    let show_player_scores = show_player_scores || split_shafts;
+    let font_size = if split_shafts {
+        hud_font_size(1, show_player_scores)
+    } else {
+        hud_font_size(player_handles.len(), show_player_scores)
+    };
     commands
         .spawn((
             InGameUi {},
@@ -36,32 +54,70 @@ pub fn build_in_game_ui(
         ))
         .with_children(|builder| {
             for handle in player_handles {
-                builder.spawn((
-                    TextBundle {
-                        text: Text {
-                            sections: vec![
-                                TextSection::new(
-                                    format!("p{}: ", handle + 1),
-                                    TextStyle {
-                                        font: ui_assets.bold_font.clone(),
-                                        font_size: 32.0,
-                                        color: Color::WHITE,
-                                        ..default()
-                                    },
-                                ),
-                                TextSection::from_style(TextStyle {
-                                    font: ui_assets.medium_font.clone(),
-                                    font_size: 32.0,
-                                    color: Color::WHITE,
-                                }),
-                            ],
-                            alignment: TextAlignment::Left,
-                            ..default()
+                let mut sections = vec![
+                    TextSection::new(
+                        format!("p{}: ", handle + 1),
+                        TextStyle {
+                            font: ui_assets.bold_font.clone(),
+                            font_size,
+                            color: Color::WHITE,
                         },
+                    ),
+                    TextSection::from_style(TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size,
+                        color: Color::WHITE,
+                    }),
+                ];
+                if show_player_scores {
+                    // p1: <health> (<score>)
+                    let style = TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size,
+                        color: Color::GRAY,
+                    };
+                    sections.push(TextSection::new(" (", style.clone()));
+                    sections.push(TextSection::from_style(style.clone()));
+                    sections.push(TextSection::new(")", style));
+                }
+
+                let text_bundle = TextBundle {
+                    text: Text {
+                        sections,
+                        alignment: TextAlignment::Left,
                         ..default()
                     },
-                    PlayerHealthText { handle },
-                ));
+                    ..default()
+                };
+                if split_shafts {
+                    // same width as the shaft below it
+                    builder
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_grow: 1.0,
+                                flex_basis: Val::Px(0.0),
+                                justify_content: JustifyContent::Center,
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        })
+                        .with_children(|builder| {
+                            builder.spawn((
+                                text_bundle,
+                                PlayerHealthText { handle },
+                                PlayerScoreText { handle },
+                            ));
+                        });
+                    continue;
+                }
+
+                let mut text_commands = builder.spawn((text_bundle, PlayerHealthText { handle }));
+                if show_player_scores {
+                    text_commands.insert(PlayerScoreText { handle });
+                }
+            }
+            if split_shafts {
+                return;
             }
 
             builder.spawn(NodeBundle {
@@ -80,14 +136,14 @@ pub fn build_in_game_ui(
                                 "score: ",
                                 TextStyle {
                                     font: ui_assets.bold_font.clone(),
-                                    font_size: 32.0,
+                                    font_size,
                                     color: Color::WHITE,
                                     ..default()
                                 },
                             ),
                             TextSection::from_style(TextStyle {
                                 font: ui_assets.medium_font.clone(),
-                                font_size: 32.0,
+                                font_size,
                                 color: Color::WHITE,
                             }),
                         ],
@@ -100,10 +156,92 @@ pub fn build_in_game_ui(
             ));
         });
 
+    commands
+        .spawn((
+            InGameUi {},
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    position_type: PositionType::Absolute,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "Waiting for peer...",
+                        TextStyle {
+                            font: ui_assets.bold_font.clone(),
+                            font_size: 32.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                NetworkStatusText {},
+            ));
+        });
+
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: ui_assets.medium_font.clone(),
+                    font_size: 14.0,
+                    color: Color::WHITE,
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(24.0),
+                    top: Val::Px(IN_GAME_UI_APP_BAR_HEIGHT + 24.0),
+                    ..default()
+                },
+                ..default()
+            },
+            background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        NetworkDiagnosticsText {},
+    ));
+
     // build_in_game_result_menu(commands, ui_assets);
 }
 
-pub fn build_in_game_result_menu(commands: &mut Commands, ui_assets: &Res<UiAssets>) {
+fn hud_font_size(num_players: usize, show_player_scores: bool) -> f32 {
+    match (num_players > 2, show_player_scores) {
+        (false, false) => 32.0,
+        (true, true) => 18.0,
+        _ => 24.0,
+    }
+}
+
+// one `p1: ` row per player, in handle order
+pub fn result_rows(num_players: usize) -> Vec<(usize, String)> {
+    (0..num_players)
+        .map(|handle| (handle, format!("p{}: ", handle + 1)))
+        .collect()
+}
+
+// `players` are (handle, label) pairs, the label is followed by that player's score
+pub fn build_in_game_result_menu(
+    commands: &mut Commands,
+    ui_assets: &Res<UiAssets>,
+    players: Vec<(usize, String)>,
+    message: Option<&str>,
+) {
+    let player_rows = if players.len() > 1 { players.len() } else { 0 };
+    let message_rows = if message.is_some() { 1 } else { 0 };
     commands
         .spawn((
             InGameResultMenuUi {},
@@ -123,7 +261,10 @@ pub fn build_in_game_result_menu(commands: &mut Commands, ui_assets: &Res<UiAsse
             builder
                 .spawn(NodeBundle {
                     style: Style {
-                        size: Size::new(Val::Px(300.0), Val::Px(360.0)),
+                        size: Size::new(
+                            Val::Px(300.0),
+                            Val::Px(360.0 + 40.0 * (player_rows + message_rows) as f32),
+                        ),
                         flex_direction: FlexDirection::Column,
                         align_items: AlignItems::Center,
                         justify_content: JustifyContent::Center,
@@ -134,6 +275,20 @@ pub fn build_in_game_result_menu(commands: &mut Commands, ui_assets: &Res<UiAsse
                     ..Default::default()
                 })
                 .with_children(|builder| {
+                    if let Some(message) = message {
+                        builder.spawn((
+                            TextBundle::from_section(
+                                message,
+                                TextStyle {
+                                    font: ui_assets.bold_font.clone(),
+                                    font_size: 20.0,
+                                    color: Color::WHITE,
+                                },
+                            ),
+                            InGameResultMenuMessageText {},
+                        ));
+                    }
+
                     builder.spawn((
                         TextBundle {
                             text: Text {
@@ -161,6 +316,36 @@ pub fn build_in_game_result_menu(commands: &mut Commands, ui_assets: &Res<UiAsse
                         InGameResultMenuScoreText {},
                     ));
 
+                    if player_rows > 0 {
+                        for (handle, label) in players {
+                            builder.spawn((
+                                TextBundle {
+                                    text: Text {
+                                        sections: vec![
+                                            TextSection::new(
+                                                label,
+                                                TextStyle {
+                                                    font: ui_assets.bold_font.clone(),
+                                                    font_size: 28.0,
+                                                    color: Color::WHITE,
+                                                },
+                                            ),
+                                            TextSection::from_style(TextStyle {
+                                                font: ui_assets.medium_font.clone(),
+                                                font_size: 28.0,
+                                                color: Color::WHITE,
+                                            }),
+                                        ],
+                                        alignment: TextAlignment::Left,
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                InGameResultMenuPlayerScoreText { handle },
+                            ));
+                        }
+                    }
+
                     builder
                         .spawn(ButtonBundle {
                             style: GLOBAL_STYLES.normal_button,
@@ -196,6 +381,18 @@ pub fn build_in_game_result_menu(commands: &mut Commands, ui_assets: &Res<UiAsse
                                 },
                             ));
                         });
+
+                    builder.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: ui_assets.medium_font.clone(),
+                                font_size: 16.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        RematchStatusText {},
+                    ));
                 });
         });
 }
@@ -209,34 +406,237 @@ pub fn despawn_in_game_result_menu(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_in_game_result_menu_if_end(
     mut commands: Commands,
-    mut player_dead_events: EventReader<PlayerEnterDeadEvent>,
+    player_dead_events: EventReader<PlayerEnterDeadEvent>,
     ui_assets: Res<UiAssets>,
     in_game_setting: Res<InGameSetting>,
     maybe_local_player_handle: Option<Res<LocalPlayerHandle>>,
+    maybe_ghost_race: Option<Res<GhostRace>>,
     menu_query: Query<Entity, With<InGameResultMenuUi>>,
+    alive_player_query: Query<&Player, Without<Dead>>,
+    versus_query: Query<(&Player, &Health, &PlayerScore, &SurvivalTicks)>,
+    scoreboard: Res<Scoreboard>,
+    maybe_match_end: Option<Res<MatchEnd>>,
+    maybe_session: Option<Res<Session<GgrsConfig>>>,
 ) {
+    if !menu_query.is_empty() {
+        return;
+    }
     match in_game_setting.mode {
         InGameMode::Offline => {
+            // local players keep going until the last one is down
             let has_dead_event = player_dead_events.len() > 0;
-            let no_menu = menu_query.is_empty();
-            if no_menu && has_dead_event {
-                build_in_game_result_menu(&mut commands, &ui_assets)
+            let all_dead = alive_player_query.is_empty();
+            if has_dead_event && all_dead && in_game_setting.is_race() {
+                // local racers are ranked like online versus
+                build_versus_result_menu(&mut commands, &ui_assets, &versus_query, None);
+            } else if has_dead_event && all_dead {
+                let ghost_message = maybe_ghost_race.map(|ghost_race| {
+                    let best_score = versus_query
+                        .iter()
+                        .map(|(_, _, x, _)| x.score)
+                        .max()
+                        .unwrap_or(0);
+                    let ghost_score = ghost_race.replay.score;
+                    match best_score.cmp(&ghost_score) {
+                        Ordering::Greater => format!("You beat the ghost's {}", ghost_score),
+                        Ordering::Equal => "Tied with the ghost".to_string(),
+                        Ordering::Less => format!("The ghost wins with {}", ghost_score),
+                    }
+                });
+                build_in_game_result_menu(
+                    &mut commands,
+                    &ui_assets,
+                    result_rows(in_game_setting.num_players),
+                    ghost_message.as_deref(),
+                )
             }
         }
+        // the end is predicted like everything else, it is only shown once no rollback
+        // can take it back
+        InGameMode::Online if !is_match_end_confirmed(maybe_match_end, maybe_session) => {}
+        // co-op goes on until the whole team is down
+        InGameMode::Online if in_game_setting.is_coop() => {
+            let message = format!("Team score: {}", scoreboard.score);
+            build_in_game_result_menu(
+                &mut commands,
+                &ui_assets,
+                result_rows(in_game_setting.num_players),
+                Some(&message),
+            )
+        }
+        // versus, the match goes on until one player is left, players and spectators
+        // who are out watch the rest
         InGameMode::Online => {
-            if let Some(local_player_handle) = maybe_local_player_handle {
-                let has_dead_event = player_dead_events
-                    .iter()
-                    .find(|x| x.handle == local_player_handle.0)
-                    .is_some();
-                let no_menu = menu_query.is_empty();
-                if no_menu && has_dead_event {
-                    build_in_game_result_menu(&mut commands, &ui_assets)
+            let local_handle = match (in_game_setting.is_spectator, maybe_local_player_handle) {
+                (false, Some(local_player_handle)) => Some(local_player_handle.0),
+                _ => None,
+            };
+            build_versus_result_menu(&mut commands, &ui_assets, &versus_query, local_handle);
+        }
+    }
+}
+
+// see `match_end_system`, spectators only ever get confirmed frames
+fn is_match_end_confirmed(
+    maybe_match_end: Option<Res<MatchEnd>>,
+    maybe_session: Option<Res<Session<GgrsConfig>>>,
+) -> bool {
+    let Some(end_frame) = maybe_match_end.and_then(|x| x.frame) else {
+        return false;
+    };
+    match maybe_session.as_deref() {
+        Some(Session::P2PSession(session)) => session.confirmed_frame() >= end_frame,
+        _ => true,
+    }
+}
+
+fn build_versus_result_menu(
+    commands: &mut Commands,
+    ui_assets: &Res<UiAssets>,
+    versus_query: &Query<(&Player, &Health, &PlayerScore, &SurvivalTicks)>,
+    local_handle: Option<usize>,
+) {
+    let ranking = versus_ranking(versus_query);
+    let message = match ranking.as_slice() {
+        [first, second, ..] if first.ticks == second.ticks => "Draw!".to_string(),
+        [first, ..] if Some(first.handle) == local_handle => "You win!".to_string(),
+        [first, ..] => format!("p{} wins!", first.handle + 1),
+        [] => "Draw!".to_string(),
+    };
+    let rows = ranking
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            let depth = x.ticks / BRICK_ROW_SPACING as u32;
+            (
+                x.handle,
+                format!("#{} p{} depth {}: ", i + 1, x.handle + 1, depth),
+            )
+        })
+        .collect();
+    build_in_game_result_menu(commands, ui_assets, rows, Some(&message))
+}
+
+struct VersusRank {
+    handle: usize,
+    ticks: u32,
+}
+
+// the last to die ranks first, only simulated state so every peer ranks the same,
+// players out on the same tick share a depth and are listed by handle
+fn versus_ranking(
+    versus_query: &Query<(&Player, &Health, &PlayerScore, &SurvivalTicks)>,
+) -> Vec<VersusRank> {
+    let mut players: Vec<_> = versus_query
+        .iter()
+        .map(|(player, _, _, ticks)| VersusRank {
+            handle: player.handle,
+            ticks: ticks.0,
+        })
+        .collect();
+    players.sort_by(|a, b| b.ticks.cmp(&a.ticks).then(a.handle.cmp(&b.handle)));
+    players
+}
+
+pub fn update_network_status_text(
+    maybe_session_status: Option<Res<SessionStatus>>,
+    mut text_query: Query<&mut Visibility, With<NetworkStatusText>>,
+) {
+    let waiting = match maybe_session_status {
+        Some(status) => !status.interrupted_peers.is_empty() && status.ended.is_none(),
+        None => false,
+    };
+    for mut visibility in text_query.iter_mut() {
+        *visibility = if waiting {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn update_network_diagnostics_text(
+    diagnostics: Res<NetworkDiagnostics>,
+    maybe_session: Option<Res<Session<GgrsConfig>>>,
+    maybe_session_status: Option<Res<SessionStatus>>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<NetworkDiagnosticsText>>,
+) {
+    let Ok((mut text, mut visibility)) = text_query.get_single_mut() else {
+        return;
+    };
+    *visibility = if diagnostics.visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if !diagnostics.visible {
+        return;
+    }
+
+    let mut lines = vec![format!(
+        "rollbacks/s: {}  prediction: {}f",
+        diagnostics.rollbacks_per_sec, diagnostics.prediction_depth
+    )];
+    match (maybe_session.as_deref(), maybe_session_status) {
+        (Some(Session::P2PSession(session)), Some(session_status)) => {
+            for (handle, addr) in session_status.players.iter().enumerate() {
+                if addr.is_none() {
+                    continue;
                 }
+                let line = match session.network_stats(handle) {
+                    Ok(stats) => format!(
+                        "P{} ping {}ms  queue {}  {}kbps  behind L{} R{}",
+                        handle + 1,
+                        stats.ping,
+                        stats.send_queue_len,
+                        stats.kbps_sent,
+                        stats.local_frames_behind,
+                        stats.remote_frames_behind
+                    ),
+                    Err(_) => format!("P{} syncing...", handle + 1),
+                };
+                lines.push(line);
             }
         }
+        (Some(Session::SpectatorSession(_)), _) => lines.push("watching".to_string()),
+        _ => lines.push("no session".to_string()),
+    }
+    text.sections[0].value = lines.join("\n");
+}
+
+pub fn update_rematch_status_text(
+    in_game_setting: Res<InGameSetting>,
+    maybe_session_status: Option<Res<SessionStatus>>,
+    maybe_rematch_status: Option<Res<RematchStatus>>,
+    mut text_query: Query<&mut Text, With<RematchStatusText>>,
+) {
+    let status = match (
+        in_game_setting.mode,
+        maybe_session_status,
+        maybe_rematch_status,
+    ) {
+        (InGameMode::Online, _, _) if in_game_setting.is_spectator => {
+            "Only players can rematch".to_string()
+        }
+        (InGameMode::Online, Some(session_status), Some(rematch_status)) => {
+            if !session_status.can_rematch() {
+                "Rematch unavailable".to_string()
+            } else if rematch_status.local_seed.is_none() {
+                "".to_string()
+            } else {
+                let num_waiting = rematch_status.num_waiting(&session_status.remote_players());
+                format!("Waiting for {} player(s)...", num_waiting)
+            }
+        }
+        _ => "".to_string(),
+    };
+    for mut text in text_query.iter_mut() {
+        if text.sections[0].value != status {
+            text.sections[0].value = status.clone();
+        }
     }
 }
 
@@ -263,17 +663,54 @@ pub fn update_health_text(
     }
 }
 
+pub fn update_player_score_text(
+    player_query: Query<(&Player, &PlayerScore)>,
+    mut text_query: Query<(&mut Text, &PlayerScoreText)>,
+) {
+    for (mut text, player_score_text) in &mut text_query {
+        let score_opt = player_query
+            .iter()
+            .find(|(x, _)| x.handle == player_score_text.handle);
+        if let Some((_, player_score)) = score_opt {
+            text.sections[3].value = player_score.score.to_string();
+        }
+    }
+}
+
 pub fn update_game_result_score_text(
     in_game_setting: Res<InGameSetting>,
     maybe_local_player_handle: Option<Res<LocalPlayerHandle>>,
     player_query: Query<(&Player, &PlayerScore)>,
     mut text_query: Query<&mut Text, With<InGameResultMenuScoreText>>,
+    mut player_text_query: Query<
+        (&mut Text, &InGameResultMenuPlayerScoreText),
+        Without<InGameResultMenuScoreText>,
+    >,
 ) {
+    for (mut text, player_score_text) in player_text_query.iter_mut() {
+        let maybe_found = player_query
+            .iter()
+            .find(|(x, _)| x.handle == player_score_text.handle);
+        if let Some((_, player_score)) = maybe_found {
+            text.sections[1].value = player_score.score.to_string();
+        }
+    }
+
     match in_game_setting.mode {
         InGameMode::Offline => {
-            for (_, player_score) in player_query.iter() {
+            // the best of the local players
+            let maybe_best = player_query.iter().map(|(_, x)| x.score).max();
+            if let Some(best_score) = maybe_best {
                 for mut text in text_query.iter_mut() {
-                    text.sections[1].value = player_score.score.to_string();
+                    text.sections[1].value = best_score.to_string();
+                }
+            }
+        }
+        InGameMode::Online if in_game_setting.is_spectator => {
+            let maybe_best = player_query.iter().map(|(_, x)| x.score).max();
+            if let Some(best_score) = maybe_best {
+                for mut text in text_query.iter_mut() {
+                    text.sections[1].value = best_score.to_string();
                 }
             }
         }
@@ -293,16 +730,37 @@ pub fn update_game_result_score_text(
 }
 
 pub fn interact_play_again_button(
+    in_game_setting: Res<InGameSetting>,
+    mut net_messenger: NetMessenger,
+    maybe_session_status: Option<Res<SessionStatus>>,
+    maybe_rematch_status: Option<ResMut<RematchStatus>>,
     mut button_query: Query<
         (&Interaction, &mut BackgroundColor),
         (Changed<Interaction>, With<InGameResultPlayAgainButton>),
     >,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
 ) {
     if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
         match *interaction {
             Interaction::Clicked => {
                 *background_color = GLOBAL_STYLES.pressed_button_color.into();
-                // app_state_next_state.set(AppState::MainMenu);
+                match in_game_setting.mode {
+                    InGameMode::Offline => {
+                        app_state_next_state.set(AppState::Rematch);
+                    }
+                    InGameMode::Online if in_game_setting.is_spectator => {}
+                    InGameMode::Online => {
+                        if let (Some(session_status), Some(mut rematch_status)) =
+                            (maybe_session_status, maybe_rematch_status)
+                        {
+                            request_rematch(
+                                &mut net_messenger,
+                                &session_status,
+                                &mut rematch_status,
+                            );
+                        }
+                    }
+                }
             }
             Interaction::Hovered => {
                 *background_color = GLOBAL_STYLES.hovered_button_color.into();
@@ -0,0 +1,224 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{camera::MainCamera, ui::*},
+    constants::AppState,
+    resources::{
+        matchmaking::{
+            MatchmakingRole, MatchmakingRoom, MatchmakingSetting, RoomCode, RoomCodeInput,
+            ROOM_CODE_LEN,
+        },
+        InGameSetting, UiAssets,
+    },
+};
+
+pub fn spawn_join_room_ui_all(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    matchmaking_setting: Res<MatchmakingSetting>,
+) {
+    let title = match matchmaking_setting.role {
+        MatchmakingRole::Player => "Enter Room Code",
+        MatchmakingRole::Spectator => "Room Code To Watch",
+    };
+    commands.spawn((Camera2dBundle::default(), MainCamera {}));
+    commands.insert_resource(RoomCodeInput::default());
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    gap: Size::new(Val::Px(0.0), Val::Px(16.0)),
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::GRAY),
+                ..Default::default()
+            },
+            JoinRoomUi {},
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font: ui_assets.bold_font.clone(),
+                    font_size: 32.0,
+                    color: Color::WHITE,
+                },
+            ));
+            builder.spawn((
+                TextBundle::from_section(
+                    "_".repeat(ROOM_CODE_LEN),
+                    TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size: 52.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                RoomCodeInputText {},
+            ));
+            builder.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size: 16.0,
+                        color: Color::ORANGE_RED,
+                    },
+                ),
+                RoomCodeErrorText {},
+            ));
+
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::new(Val::Px(8.0), Val::Px(0.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    builder
+                        .spawn((
+                            ButtonBundle {
+                                style: GLOBAL_STYLES.normal_button_medium,
+                                background_color: GLOBAL_STYLES.normal_button_color.into(),
+                                ..default()
+                            },
+                            BackMainMenuButton {},
+                        ))
+                        .with_children(|builder| {
+                            builder.spawn(TextBundle::from_section(
+                                "Cancel",
+                                TextStyle {
+                                    font: ui_assets.bold_font.clone(),
+                                    font_size: 16.0,
+                                    color: Color::WHITE,
+                                },
+                            ));
+                        });
+
+                    builder
+                        .spawn((
+                            ButtonBundle {
+                                style: GLOBAL_STYLES.normal_button_medium,
+                                background_color: GLOBAL_STYLES.normal_button_color.into(),
+                                ..default()
+                            },
+                            JoinRoomConfirmButton {},
+                        ))
+                        .with_children(|builder| {
+                            builder.spawn(TextBundle::from_section(
+                                "Join",
+                                TextStyle {
+                                    font: ui_assets.bold_font.clone(),
+                                    font_size: 16.0,
+                                    color: Color::WHITE,
+                                },
+                            ));
+                        });
+                });
+        });
+}
+
+pub fn despawn_join_room_ui_all(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<JoinRoomUi>, With<MainCamera>)>>,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<RoomCodeInput>();
+}
+
+pub fn input_room_code_system(
+    mut char_events: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut room_code_input: ResMut<RoomCodeInput>,
+) {
+    for event in char_events.iter() {
+        if event.char.is_ascii_alphanumeric() && room_code_input.value.len() < ROOM_CODE_LEN {
+            room_code_input.value.push(event.char.to_ascii_uppercase());
+            room_code_input.invalid = false;
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        room_code_input.value.pop();
+        room_code_input.invalid = false;
+    }
+}
+
+pub fn update_room_code_text(
+    room_code_input: Res<RoomCodeInput>,
+    mut text_query: Query<&mut Text, (With<RoomCodeInputText>, Without<RoomCodeErrorText>)>,
+    mut error_text_query: Query<&mut Text, (With<RoomCodeErrorText>, Without<RoomCodeInputText>)>,
+) {
+    if !room_code_input.is_changed() {
+        return;
+    }
+    for mut text in text_query.iter_mut() {
+        let padding = ROOM_CODE_LEN.saturating_sub(room_code_input.value.len());
+        text.sections[0].value = format!("{}{}", room_code_input.value, "_".repeat(padding));
+    }
+    for mut text in error_text_query.iter_mut() {
+        text.sections[0].value = if room_code_input.invalid {
+            "Invalid room code".to_string()
+        } else {
+            "".to_string()
+        };
+    }
+}
+
+pub fn interact_with_join_room_confirm_button(
+    keys: Res<Input<KeyCode>>,
+    mut room_code_input: ResMut<RoomCodeInput>,
+    mut in_game_setting: ResMut<InGameSetting>,
+    mut matchmaking_setting: ResMut<MatchmakingSetting>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<JoinRoomConfirmButton>),
+    >,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    let mut confirmed = keys.just_pressed(KeyCode::Return);
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                confirmed = true;
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+
+    if !confirmed {
+        return;
+    }
+    match RoomCode::parse(&room_code_input.value) {
+        Some(code) => {
+            matchmaking_setting.num_players = code.num_players();
+            in_game_setting.rules = code.rules();
+            matchmaking_setting.room = MatchmakingRoom::Private(code);
+            match matchmaking_setting.role {
+                MatchmakingRole::Player => {
+                    in_game_setting.set_online(matchmaking_setting.num_players);
+                }
+                MatchmakingRole::Spectator => {
+                    in_game_setting.set_online_spectator(matchmaking_setting.num_players);
+                }
+            }
+            app_state_next_state.set(AppState::Matchmaking);
+        }
+        None => {
+            room_code_input.invalid = true;
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{camera::MainCamera, ui::*},
+    constants::LAN_PORT,
+    resources::{
+        lan_lobby::{LanAddressInput, LanDiscovery, LanLobby, LanLobbyMode},
+        matchmaking::MatchmakingSetting,
+        GameRules, InGameSetting, UiAssets,
+    },
+    systems::{
+        lan_systems::{host_lan_game, join_lan_game},
+        ui::main_menu_ui_systems::spawn_medium_button,
+    },
+};
+
+// "255.255.255.255:65535"
+const LAN_ADDRESS_MAX_LEN: usize = 21;
+
+pub fn spawn_lan_lobby_ui_all(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    in_game_setting: Res<InGameSetting>,
+    matchmaking_setting: Res<MatchmakingSetting>,
+) {
+    commands.spawn((Camera2dBundle::default(), MainCamera {}));
+    commands.insert_resource(LanAddressInput::default());
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    gap: Size::new(Val::Px(0.0), Val::Px(16.0)),
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::GRAY),
+                ..Default::default()
+            },
+            LanLobbyUi {},
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                "LAN Play",
+                TextStyle {
+                    font: ui_assets.bold_font.clone(),
+                    font_size: 52.0,
+                    color: Color::WHITE,
+                },
+            ));
+            spawn_medium_button(
+                builder,
+                &ui_assets,
+                &format!(
+                    "Host {}P{}",
+                    matchmaking_setting.num_players,
+                    rules_label(in_game_setting.rules)
+                ),
+                LanHostButton {},
+            );
+
+            builder.spawn(TextBundle::from_section(
+                "Games Nearby",
+                TextStyle {
+                    font: ui_assets.bold_font.clone(),
+                    font_size: 32.0,
+                    color: Color::WHITE,
+                },
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        gap: Size::new(Val::Px(0.0), Val::Px(8.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                LanHostList {},
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Direct Connect",
+                TextStyle {
+                    font: ui_assets.bold_font.clone(),
+                    font_size: 32.0,
+                    color: Color::WHITE,
+                },
+            ));
+            builder.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size: 32.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                LanAddressInputText {},
+            ));
+
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::new(Val::Px(8.0), Val::Px(0.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    spawn_medium_button(builder, &ui_assets, "Cancel", BackMainMenuButton {});
+                    spawn_medium_button(builder, &ui_assets, "Connect", LanConnectButton {});
+                });
+
+            builder.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                LanStatusText {},
+            ));
+        });
+}
+
+pub fn despawn_lan_lobby_ui_all(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<LanLobbyUi>, With<MainCamera>)>>,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<LanAddressInput>();
+}
+
+pub fn update_lan_host_list(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    discovery: Res<LanDiscovery>,
+    list_query: Query<Entity, With<LanHostList>>,
+) {
+    if !discovery.is_changed() {
+        return;
+    }
+    for entity in list_query.iter() {
+        commands.entity(entity).despawn_descendants();
+        commands.entity(entity).with_children(|builder| {
+            if discovery.socket.is_none() {
+                builder.spawn(TextBundle::from_section(
+                    "Discovery unavailable",
+                    TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                ));
+                return;
+            }
+            if discovery.hosts.is_empty() {
+                builder.spawn(TextBundle::from_section(
+                    "Searching...",
+                    TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                ));
+            }
+            for host in discovery.hosts.iter() {
+                builder
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(300.0), Val::Px(40.0)),
+                                ..GLOBAL_STYLES.normal_button_medium
+                            },
+                            background_color: GLOBAL_STYLES.normal_button_color.into(),
+                            ..default()
+                        },
+                        LanJoinHostButton { addr: host.addr },
+                    ))
+                    .with_children(|builder| {
+                        builder.spawn(TextBundle::from_section(
+                            format!(
+                                "{} ({}/{}P{})",
+                                host.addr.ip(),
+                                host.num_joined,
+                                host.num_players,
+                                rules_label(host.rules)
+                            ),
+                            TextStyle {
+                                font: ui_assets.bold_font.clone(),
+                                font_size: 16.0,
+                                color: Color::WHITE,
+                            },
+                        ));
+                    });
+            }
+        });
+    }
+}
+
+pub fn input_lan_address_system(
+    mut char_events: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut address_input: ResMut<LanAddressInput>,
+) {
+    for event in char_events.iter() {
+        let is_address_char = event.char.is_ascii_digit() || event.char == '.' || event.char == ':';
+        if is_address_char && address_input.value.len() < LAN_ADDRESS_MAX_LEN {
+            address_input.value.push(event.char);
+            address_input.invalid = false;
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        address_input.value.pop();
+        address_input.invalid = false;
+    }
+}
+
+pub fn update_lan_address_text(
+    address_input: Res<LanAddressInput>,
+    mut text_query: Query<&mut Text, With<LanAddressInputText>>,
+) {
+    if !address_input.is_changed() {
+        return;
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = if address_input.value.is_empty() {
+            "IP:port".to_string()
+        } else {
+            address_input.value.clone()
+        };
+    }
+}
+
+fn rules_label(rules: GameRules) -> String {
+    match rules {
+        GameRules::Versus => "".to_string(),
+        _ => format!(" {}", rules.label().to_lowercase()),
+    }
+}
+
+pub fn update_lan_status_text(
+    lobby: Res<LanLobby>,
+    address_input: Res<LanAddressInput>,
+    matchmaking_setting: Res<MatchmakingSetting>,
+    mut text_query: Query<&mut Text, With<LanStatusText>>,
+) {
+    if !lobby.is_changed() && !address_input.is_changed() {
+        return;
+    }
+    let status = match &lobby.mode {
+        LanLobbyMode::Idle => match &lobby.error {
+            Some(error) => error.clone(),
+            None if address_input.invalid => "Invalid address".to_string(),
+            None => "".to_string(),
+        },
+        LanLobbyMode::Hosting { joiners } => format!(
+            "Hosting on port {}, {}/{} players",
+            LAN_PORT,
+            joiners.len() + 1,
+            matchmaking_setting.num_players
+        ),
+        LanLobbyMode::Starting { .. } => "Starting...".to_string(),
+        LanLobbyMode::Joining { host } => format!("Connecting to {}...", host),
+    };
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = status.clone();
+    }
+}
+
+pub fn interact_with_lan_host_button(
+    mut commands: Commands,
+    mut lobby: ResMut<LanLobby>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<LanHostButton>),
+    >,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                if lobby.mode == LanLobbyMode::Idle {
+                    host_lan_game(&mut commands, &mut lobby);
+                }
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_lan_join_host_button(
+    mut commands: Commands,
+    mut lobby: ResMut<LanLobby>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor, &LanJoinHostButton),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, mut background_color, join_host_button) in button_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                if lobby.mode == LanLobbyMode::Idle {
+                    join_lan_game(&mut commands, &mut lobby, join_host_button.addr);
+                }
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_lan_connect_button(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut lobby: ResMut<LanLobby>,
+    mut address_input: ResMut<LanAddressInput>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<LanConnectButton>),
+    >,
+) {
+    let mut confirmed = keys.just_pressed(KeyCode::Return);
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                confirmed = true;
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+
+    if !confirmed || lobby.mode != LanLobbyMode::Idle {
+        return;
+    }
+    match address_input.parse(LAN_PORT) {
+        Some(host) => join_lan_game(&mut commands, &mut lobby, host),
+        None => address_input.invalid = true,
+    }
+}
@@ -3,10 +3,19 @@ use bevy::{app::AppExit, prelude::*};
 use crate::{
     components::{
         camera::MainCamera,
-        ui::{MainMenuUi, OnlinePlayButton, QuitButton, SinglePlayButton, GLOBAL_STYLES},
+        ui::{
+            ControlsButton, CreateRoomButton, JoinRoomButton, LanPlayButton, LocalPlayersButton,
+            MainMenuUi, OnlinePlayersButton, QuickMatchButton, QuitButton, RaceGhostButton,
+            RulesButton, SettingsButton, SinglePlayButton, WatchRoomButton, GLOBAL_STYLES,
+        },
     },
-    constants::AppState,
-    resources::{InGameSetting, UiAssets},
+    constants::{AppState, MAX_LOCAL_PLAYERS, MAX_ONLINE_PLAYERS, MIN_ONLINE_PLAYERS},
+    resources::{
+        matchmaking::{MatchmakingRole, MatchmakingRoom, MatchmakingSetting, RoomCode},
+        session_config::LaunchArgs,
+        GameRules, InGameSetting, UiAssets,
+    },
+    systems::ghost_systems::start_ghost_race,
 };
 
 pub fn spawn_main_menu_ui_all(mut commands: Commands, ui_assets: Res<UiAssets>) {
@@ -98,25 +107,122 @@ fn build_main_menu_ui(commands: &mut Commands, ui_assets: &Res<UiAssets>) {
                         },
                     ));
                 });
-
             builder
-                .spawn((
-                    ButtonBundle {
-                        style: GLOBAL_STYLES.normal_button,
-                        background_color: GLOBAL_STYLES.normal_button_color.into(),
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::new(Val::Px(8.0), Val::Px(0.0)),
                         ..default()
                     },
-                    OnlinePlayButton {},
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Online Matching",
-                        TextStyle {
-                            font: ui_assets.bold_font.clone(),
-                            font_size: 32.0,
-                            color: Color::WHITE,
+                    ..default()
+                })
+                .with_children(|builder| {
+                    for rules in GameRules::ALL {
+                        spawn_medium_button(
+                            builder,
+                            ui_assets,
+                            rules.label(),
+                            RulesButton { rules },
+                        );
+                    }
+                });
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::new(Val::Px(8.0), Val::Px(0.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    spawn_medium_button(
+                        builder,
+                        ui_assets,
+                        &format!("Local {}P", MAX_LOCAL_PLAYERS),
+                        LocalPlayersButton {
+                            num_players: MAX_LOCAL_PLAYERS,
                         },
-                    ));
+                    );
+                    // ghosts are replay files, which the browser doesn't have
+                    #[cfg(not(target_arch = "wasm32"))]
+                    spawn_medium_button(builder, ui_assets, "Race Ghost", RaceGhostButton {});
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "Online Matching",
+                TextStyle {
+                    font: ui_assets.bold_font.clone(),
+                    font_size: 32.0,
+                    color: Color::WHITE,
+                },
+            ));
+
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::new(Val::Px(8.0), Val::Px(0.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    for num_players in MIN_ONLINE_PLAYERS..=MAX_ONLINE_PLAYERS {
+                        builder
+                            .spawn((
+                                ButtonBundle {
+                                    style: GLOBAL_STYLES.normal_button_medium,
+                                    background_color: GLOBAL_STYLES.normal_button_color.into(),
+                                    ..default()
+                                },
+                                OnlinePlayersButton { num_players },
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    format!("{}P", num_players),
+                                    TextStyle {
+                                        font: ui_assets.bold_font.clone(),
+                                        font_size: 24.0,
+                                        color: Color::WHITE,
+                                    },
+                                ));
+                            });
+                    }
+                });
+
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::new(Val::Px(8.0), Val::Px(0.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    spawn_medium_button(builder, ui_assets, "Quick Match", QuickMatchButton {});
+                    spawn_medium_button(builder, ui_assets, "Create Room", CreateRoomButton {});
+                    spawn_medium_button(builder, ui_assets, "Join Room", JoinRoomButton {});
+                    spawn_medium_button(builder, ui_assets, "Watch Room", WatchRoomButton {});
+                });
+
+            // direct udp sockets are not available in the browser
+            #[cfg(not(target_arch = "wasm32"))]
+            spawn_medium_button(builder, ui_assets, "LAN Play", LanPlayButton {});
+
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::new(Val::Px(8.0), Val::Px(0.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    spawn_medium_button(builder, ui_assets, "Settings", SettingsButton {});
+                    spawn_medium_button(builder, ui_assets, "Controls", ControlsButton {});
                 });
 
             builder
@@ -141,6 +247,33 @@ fn build_main_menu_ui(commands: &mut Commands, ui_assets: &Res<UiAssets>) {
         });
 }
 
+pub fn spawn_medium_button(
+    builder: &mut ChildBuilder,
+    ui_assets: &Res<UiAssets>,
+    label: &str,
+    marker: impl Component,
+) {
+    builder
+        .spawn((
+            ButtonBundle {
+                style: GLOBAL_STYLES.normal_button_medium,
+                background_color: GLOBAL_STYLES.normal_button_color.into(),
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: ui_assets.bold_font.clone(),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
 pub fn interact_with_single_play_button(
     mut in_game_setting: ResMut<InGameSetting>,
     mut button_query: Query<
@@ -166,11 +299,140 @@ pub fn interact_with_single_play_button(
     }
 }
 
-pub fn interact_with_online_play_button(
+pub fn interact_with_local_players_button(
+    mut in_game_setting: ResMut<InGameSetting>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor, &LocalPlayersButton),
+        Changed<Interaction>,
+    >,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, mut background_color, local_players_button) in button_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                in_game_setting.set_offline_local(local_players_button.num_players);
+                app_state_next_state.set(AppState::InGame);
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_race_ghost_button(
+    mut commands: Commands,
+    launch_args: Res<LaunchArgs>,
+    mut in_game_setting: ResMut<InGameSetting>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<RaceGhostButton>),
+    >,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                start_ghost_race(
+                    &mut commands,
+                    &launch_args,
+                    &mut in_game_setting,
+                    &mut app_state_next_state,
+                );
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_online_players_button(
+    mut matchmaking_setting: ResMut<MatchmakingSetting>,
+    mut button_query: Query<(&Interaction, &mut BackgroundColor, &OnlinePlayersButton)>,
+) {
+    for (interaction, _, online_players_button) in button_query.iter() {
+        if *interaction == Interaction::Clicked {
+            matchmaking_setting.num_players = online_players_button.num_players;
+        }
+    }
+    for (interaction, mut background_color, online_players_button) in button_query.iter_mut() {
+        if online_players_button.num_players == matchmaking_setting.num_players {
+            *background_color = GLOBAL_STYLES.pressed_button_color.into();
+            continue;
+        }
+        *background_color = match *interaction {
+            Interaction::Hovered => GLOBAL_STYLES.hovered_button_color.into(),
+            _ => GLOBAL_STYLES.normal_button_color.into(),
+        };
+    }
+}
+
+pub fn interact_with_rules_button(
+    mut in_game_setting: ResMut<InGameSetting>,
+    mut button_query: Query<(&Interaction, &mut BackgroundColor, &RulesButton)>,
+) {
+    for (interaction, _, rules_button) in button_query.iter() {
+        if *interaction == Interaction::Clicked {
+            in_game_setting.rules = rules_button.rules;
+        }
+    }
+    for (interaction, mut background_color, rules_button) in button_query.iter_mut() {
+        if rules_button.rules == in_game_setting.rules {
+            *background_color = GLOBAL_STYLES.pressed_button_color.into();
+            continue;
+        }
+        *background_color = match *interaction {
+            Interaction::Hovered => GLOBAL_STYLES.hovered_button_color.into(),
+            _ => GLOBAL_STYLES.normal_button_color.into(),
+        };
+    }
+}
+
+pub fn interact_with_quick_match_button(
+    mut in_game_setting: ResMut<InGameSetting>,
+    mut matchmaking_setting: ResMut<MatchmakingSetting>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<QuickMatchButton>),
+    >,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                matchmaking_setting.room = MatchmakingRoom::QuickMatch {
+                    rules: in_game_setting.rules,
+                };
+                matchmaking_setting.role = MatchmakingRole::Player;
+                in_game_setting.set_online(matchmaking_setting.num_players);
+                app_state_next_state.set(AppState::Matchmaking);
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_create_room_button(
     mut in_game_setting: ResMut<InGameSetting>,
+    mut matchmaking_setting: ResMut<MatchmakingSetting>,
     mut button_query: Query<
         (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<OnlinePlayButton>),
+        (Changed<Interaction>, With<CreateRoomButton>),
     >,
     mut app_state_next_state: ResMut<NextState<AppState>>,
 ) {
@@ -178,7 +440,14 @@ pub fn interact_with_online_play_button(
         match *interaction {
             Interaction::Clicked => {
                 *background_color = GLOBAL_STYLES.pressed_button_color.into();
-                in_game_setting.set_online_2p();
+                let code = RoomCode::generate(
+                    matchmaking_setting.num_players,
+                    in_game_setting.rules,
+                    &fastrand::Rng::new(),
+                );
+                matchmaking_setting.room = MatchmakingRoom::Private(code);
+                matchmaking_setting.role = MatchmakingRole::Player;
+                in_game_setting.set_online(matchmaking_setting.num_players);
                 app_state_next_state.set(AppState::Matchmaking);
             }
             Interaction::Hovered => {
@@ -191,6 +460,125 @@ pub fn interact_with_online_play_button(
     }
 }
 
+pub fn interact_with_join_room_button(
+    mut matchmaking_setting: ResMut<MatchmakingSetting>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<JoinRoomButton>),
+    >,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                matchmaking_setting.role = MatchmakingRole::Player;
+                app_state_next_state.set(AppState::JoinRoom);
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_watch_room_button(
+    mut matchmaking_setting: ResMut<MatchmakingSetting>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<WatchRoomButton>),
+    >,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                matchmaking_setting.role = MatchmakingRole::Spectator;
+                app_state_next_state.set(AppState::JoinRoom);
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_lan_play_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<LanPlayButton>),
+    >,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                app_state_next_state.set(AppState::LanLobby);
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_settings_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<SettingsButton>),
+    >,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                app_state_next_state.set(AppState::Settings);
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_controls_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ControlsButton>),
+    >,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                app_state_next_state.set(AppState::Controls);
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
 pub fn interact_with_quit_button(
     mut button_query: Query<
         (&Interaction, &mut BackgroundColor),
@@ -2,11 +2,23 @@ use bevy::{prelude::*, time::Stopwatch};
 
 use crate::{
     components::{camera::MainCamera, ui::*},
-    constants::AppState,
-    resources::UiAssets,
+    constants::{AppState, LOBBY_COUNTDOWN},
+    resources::{
+        matchmaking::{
+            MatchLobby, MatchmakingRole, MatchmakingRoom, MatchmakingSetting, MatchmakingStatus,
+        },
+        AppConfig, AppConfigAssets, UiAssets,
+    },
+    systems::{
+        network_systems::open_matchbox_socket, ui::main_menu_ui_systems::spawn_medium_button,
+    },
 };
 
-pub fn spawn_matchmaking_ui_all(mut commands: Commands, ui_assets: Res<UiAssets>) {
+pub fn spawn_matchmaking_ui_all(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    matchmaking_setting: Res<MatchmakingSetting>,
+) {
     commands.spawn((Camera2dBundle::default(), MainCamera {}));
     commands.spawn(MatchingTimer {
         elapsed_timer: Stopwatch::new(),
@@ -28,8 +40,43 @@ pub fn spawn_matchmaking_ui_all(mut commands: Commands, ui_assets: Res<UiAssets>
             MatchmakingUi {},
         ))
         .with_children(|builder| {
+            if let MatchmakingRoom::Private(code) = &matchmaking_setting.room {
+                builder.spawn(TextBundle::from_section(
+                    "Room Code",
+                    TextStyle {
+                        font: ui_assets.bold_font.clone(),
+                        font_size: 32.0,
+                        color: Color::WHITE,
+                    },
+                ));
+                builder.spawn((
+                    TextBundle::from_section(
+                        code.as_str(),
+                        TextStyle {
+                            font: ui_assets.medium_font.clone(),
+                            font_size: 70.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    MatchmakingRoomCodeText {},
+                ));
+                builder.spawn(TextBundle::from_section(
+                    "Share this code with your friends",
+                    TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                ));
+            }
+            let status = match matchmaking_setting.role {
+                MatchmakingRole::Player => {
+                    format!("Matching {}P...", matchmaking_setting.num_players)
+                }
+                MatchmakingRole::Spectator => "Waiting for match...".to_string(),
+            };
             builder.spawn(TextBundle::from_section(
-                "Matching...",
+                status,
                 TextStyle {
                     font: ui_assets.bold_font.clone(),
                     font_size: 32.0,
@@ -48,24 +95,100 @@ pub fn spawn_matchmaking_ui_all(mut commands: Commands, ui_assets: Res<UiAssets>
                 MatchingElapsedText {},
             ));
 
-            builder
-                .spawn((
-                    ButtonBundle {
-                        style: GLOBAL_STYLES.normal_button_medium,
-                        background_color: GLOBAL_STYLES.normal_button_color.into(),
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        gap: Size::new(Val::Px(0.0), Val::Px(8.0)),
                         ..default()
                     },
-                    BackMainMenuButton {},
-                ))
-                .with_children(|builder| {
-                    builder.spawn(TextBundle::from_section(
-                        "Cancel",
+                    ..default()
+                },
+                MatchmakingPlayerList {},
+            ));
+            builder.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: ui_assets.bold_font.clone(),
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                MatchmakingCountdownText {},
+            ));
+
+            builder.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "",
                         TextStyle {
-                            font: ui_assets.bold_font.clone(),
-                            font_size: 16.0,
-                            color: Color::WHITE,
+                            font: ui_assets.medium_font.clone(),
+                            font_size: 24.0,
+                            color: Color::ORANGE_RED,
                         },
-                    ));
+                    ),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                MatchmakingErrorText {},
+            ));
+
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        gap: Size::new(Val::Px(8.0), Val::Px(0.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    spawn_medium_button(builder, &ui_assets, "Cancel", BackMainMenuButton {});
+                    builder
+                        .spawn((
+                            ButtonBundle {
+                                style: GLOBAL_STYLES.normal_button_medium,
+                                background_color: GLOBAL_STYLES.normal_button_color.into(),
+                                visibility: Visibility::Hidden,
+                                ..default()
+                            },
+                            MatchmakingReadyButton {},
+                        ))
+                        .with_children(|builder| {
+                            builder.spawn((
+                                TextBundle::from_section(
+                                    "Ready",
+                                    TextStyle {
+                                        font: ui_assets.bold_font.clone(),
+                                        font_size: 16.0,
+                                        color: Color::WHITE,
+                                    },
+                                ),
+                                MatchmakingReadyText {},
+                            ));
+                        });
+                    builder
+                        .spawn((
+                            ButtonBundle {
+                                style: GLOBAL_STYLES.normal_button_medium,
+                                background_color: GLOBAL_STYLES.normal_button_color.into(),
+                                visibility: Visibility::Hidden,
+                                ..default()
+                            },
+                            MatchmakingRetryButton {},
+                        ))
+                        .with_children(|builder| {
+                            builder.spawn(TextBundle::from_section(
+                                "Retry",
+                                TextStyle {
+                                    font: ui_assets.bold_font.clone(),
+                                    font_size: 16.0,
+                                    color: Color::WHITE,
+                                },
+                            ));
+                        });
                 });
         });
 }
@@ -89,11 +212,14 @@ pub fn despawn_matchmaking_ui_all(
 
 pub fn update_matching_elapsed_time(
     time: Res<Time>,
+    status: Res<MatchmakingStatus>,
     mut text_query: Query<&mut Text, With<MatchingElapsedText>>,
     mut timer_query: Query<&mut MatchingTimer>,
 ) {
     let mut timer = timer_query.single_mut();
-    timer.elapsed_timer.tick(time.delta());
+    if status.error.is_none() {
+        timer.elapsed_timer.tick(time.delta());
+    }
     for mut text in text_query.iter_mut() {
         text.sections[0].value = format!(
             "{} seconds elapsed",
@@ -102,6 +228,175 @@ pub fn update_matching_elapsed_time(
     }
 }
 
+pub fn update_matchmaking_lobby_ui(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    matchmaking_setting: Res<MatchmakingSetting>,
+    lobby: Res<MatchLobby>,
+    list_query: Query<Entity, With<MatchmakingPlayerList>>,
+    mut button_query: Query<&mut Visibility, With<MatchmakingReadyButton>>,
+    mut text_query: Query<&mut Text, With<MatchmakingReadyText>>,
+) {
+    if !lobby.is_changed() {
+        return;
+    }
+    for entity in list_query.iter() {
+        commands.entity(entity).despawn_descendants();
+        commands.entity(entity).with_children(|builder| {
+            for peer in lobby.players.iter() {
+                let nickname = lobby.nicknames.get(peer).map_or("...", |x| x.as_str());
+                let you = if lobby.local_id == Some(*peer) {
+                    " (you)"
+                } else {
+                    ""
+                };
+                let (ready, color) = if lobby.is_ready(*peer) {
+                    ("Ready", Color::LIME_GREEN)
+                } else {
+                    ("Not ready", Color::WHITE)
+                };
+                builder.spawn(TextBundle::from_section(
+                    format!("{}{}: {}", nickname, you, ready),
+                    TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size: 24.0,
+                        color,
+                    },
+                ));
+            }
+        });
+    }
+
+    // spectators just wait for the players
+    let can_ready =
+        matchmaking_setting.role == MatchmakingRole::Player && !lobby.players.is_empty();
+    for mut visibility in button_query.iter_mut() {
+        *visibility = if can_ready {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = if lobby.local_ready {
+            "Not Ready".to_string()
+        } else {
+            "Ready".to_string()
+        };
+    }
+}
+
+pub fn update_matchmaking_countdown_text(
+    time: Res<Time>,
+    lobby: Res<MatchLobby>,
+    mut text_query: Query<&mut Text, With<MatchmakingCountdownText>>,
+) {
+    let value = match lobby.countdown_started_at {
+        Some(started_at) => {
+            let remaining = LOBBY_COUNTDOWN - (time.elapsed_seconds() - started_at);
+            format!("Starting in {}", remaining.max(0.0).ceil())
+        }
+        None if !lobby.players.is_empty() => "Waiting for everyone to be ready".to_string(),
+        None => "".to_string(),
+    };
+    for mut text in text_query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+pub fn interact_with_matchmaking_ready_button(
+    mut lobby: ResMut<MatchLobby>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<MatchmakingReadyButton>),
+    >,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                if !lobby.players.is_empty() {
+                    lobby.local_ready = !lobby.local_ready;
+                }
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
+pub fn update_matchmaking_error_ui(
+    status: Res<MatchmakingStatus>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<MatchmakingErrorText>>,
+    mut button_query: Query<
+        &mut Visibility,
+        (With<MatchmakingRetryButton>, Without<MatchmakingErrorText>),
+    >,
+) {
+    if !status.is_changed() {
+        return;
+    }
+    let visibility = match status.error {
+        Some(_) => Visibility::Inherited,
+        None => Visibility::Hidden,
+    };
+    for (mut text, mut text_visibility) in text_query.iter_mut() {
+        text.sections[0].value = status.error.clone().unwrap_or_default();
+        *text_visibility = visibility;
+    }
+    for mut button_visibility in button_query.iter_mut() {
+        *button_visibility = visibility;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn interact_with_matchmaking_retry_button(
+    mut commands: Commands,
+    time: Res<Time>,
+    matchmaking_setting: Res<MatchmakingSetting>,
+    app_config_assets: Res<AppConfigAssets>,
+    assets: Res<Assets<AppConfig>>,
+    mut status: ResMut<MatchmakingStatus>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<MatchmakingRetryButton>),
+    >,
+    mut timer_query: Query<&mut MatchingTimer>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *background_color = GLOBAL_STYLES.pressed_button_color.into();
+                if status.error.is_none() {
+                    return;
+                }
+                info!("retrying matchmaking");
+                *status = open_matchbox_socket(
+                    &mut commands,
+                    &matchmaking_setting,
+                    assets.get(&app_config_assets.main),
+                    time.elapsed_seconds(),
+                );
+                for mut timer in timer_query.iter_mut() {
+                    timer.elapsed_timer.reset();
+                }
+            }
+            Interaction::Hovered => {
+                *background_color = GLOBAL_STYLES.hovered_button_color.into();
+            }
+            Interaction::None => {
+                *background_color = GLOBAL_STYLES.normal_button_color.into();
+            }
+        }
+    }
+}
+
 pub fn interact_with_back_main_menu_button(
     mut button_query: Query<
         (&Interaction, &mut BackgroundColor),
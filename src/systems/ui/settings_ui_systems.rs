@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{camera::MainCamera, ui::*},
+    resources::{session_config::GgrsSessionConfig, UiAssets},
+    systems::ui::main_menu_ui_systems::spawn_medium_button,
+};
+
+pub fn spawn_settings_ui_all(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    session_config: Res<GgrsSessionConfig>,
+) {
+    let desync_detection = match session_config.desync_detection_interval {
+        0 => "off".to_string(),
+        interval => format!("every {} frames", interval),
+    };
+    let simulator = &session_config.network_simulator;
+    let network_simulator = if simulator.enabled {
+        format!(
+            "{}±{} ms, {:.0}% loss, {:.0}% reorder",
+            simulator.latency_ms,
+            simulator.jitter_ms,
+            simulator.packet_loss * 100.0,
+            simulator.reorder * 100.0
+        )
+    } else {
+        "off".to_string()
+    };
+    let rows = [
+        (
+            "Input delay",
+            format!("{} frames", session_config.input_delay),
+        ),
+        (
+            "Max prediction",
+            format!("{} frames", session_config.max_prediction),
+        ),
+        ("Desync detection", desync_detection),
+        (
+            "Disconnect timeout",
+            format!("{} ms", session_config.disconnect_timeout_ms),
+        ),
+        (
+            "Disconnect notify",
+            format!("{} ms", session_config.disconnect_notify_start_ms),
+        ),
+        ("Network simulator", network_simulator),
+    ];
+
+    commands.spawn((Camera2dBundle::default(), MainCamera {}));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    gap: Size::new(Val::Px(0.0), Val::Px(16.0)),
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::GRAY),
+                ..Default::default()
+            },
+            SettingsUi {},
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                "Settings",
+                TextStyle {
+                    font: ui_assets.bold_font.clone(),
+                    font_size: 52.0,
+                    color: Color::WHITE,
+                },
+            ));
+            builder.spawn(TextBundle::from_section(
+                "Online Session",
+                TextStyle {
+                    font: ui_assets.bold_font.clone(),
+                    font_size: 32.0,
+                    color: Color::WHITE,
+                },
+            ));
+            for (label, value) in rows {
+                builder.spawn(TextBundle::from_section(
+                    format!("{}: {}", label, value),
+                    TextStyle {
+                        font: ui_assets.medium_font.clone(),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                ));
+            }
+            builder.spawn(TextBundle::from_section(
+                "Edit configs/main.app_config.toml\nor launch with e.g. --input-delay 3",
+                TextStyle {
+                    font: ui_assets.medium_font.clone(),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                },
+            ));
+            spawn_medium_button(builder, &ui_assets, "Back", BackMainMenuButton {});
+        });
+}
+
+pub fn despawn_settings_ui_all(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<SettingsUi>, With<MainCamera>)>>,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    components::ui::VirtualMoveButton,
+    resources::{touch_zones::VirtualButtonsConfig, InGameSetting, UiAssets},
+    utils::input_utils::touch_position,
+};
+
+pub fn spawn_virtual_buttons(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    config: Res<VirtualButtonsConfig>,
+    in_game_setting: Res<InGameSetting>,
+) {
+    // local multiplayer shares the screen with touch zones instead
+    if !config.enabled || in_game_setting.num_local_players() != 1 {
+        return;
+    }
+
+    for (label, move_x) in [("<", -1.0), (">", 1.0)] {
+        let position = if move_x < 0.0 {
+            UiRect {
+                left: Val::Px(config.margin),
+                bottom: Val::Px(config.bottom),
+                ..default()
+            }
+        } else {
+            UiRect {
+                right: Val::Px(config.margin),
+                bottom: Val::Px(config.bottom),
+                ..default()
+            }
+        };
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(config.size), Val::Px(config.size)),
+                        position_type: PositionType::Absolute,
+                        position,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    background_color: Color::rgba(1.0, 1.0, 1.0, config.opacity).into(),
+                    ..default()
+                },
+                VirtualMoveButton { move_x },
+            ))
+            .with_children(|builder| {
+                builder.spawn(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font: ui_assets.bold_font.clone(),
+                        font_size: config.size / 2.0,
+                        color: Color::rgba(0.0, 0.0, 0.0, config.opacity),
+                    },
+                ));
+            });
+    }
+}
+
+// only the pressed feedback, `LocalInput` reads the buttons itself when it samples
+pub fn virtual_buttons_system(
+    config: Res<VirtualButtonsConfig>,
+    touches: Res<Touches>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    mut button_query: Query<(&VirtualMoveButton, &mut BackgroundColor)>,
+) {
+    let Ok(window) = primary_query.get_single() else {
+        return;
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+
+    for (button, mut background_color) in button_query.iter_mut() {
+        let rect = config.button_rect(button.move_x, window_size);
+        let pressed = touches
+            .iter()
+            .any(|touch| rect.contains(touch_position(touch.position(), window)));
+
+        let opacity = if pressed {
+            (config.opacity * 2.0).min(1.0)
+        } else {
+            config.opacity
+        };
+        let color = Color::rgba(1.0, 1.0, 1.0, opacity);
+        if background_color.0 != color {
+            background_color.0 = color;
+        }
+    }
+}
@@ -1,102 +1,63 @@
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 use bevy_ggrs::PlayerInputs;
 
 use crate::{
     components::{player::Player, userinput::Userinput},
-    constants::{GgrsConfig, INPUT_LEFT, INPUT_RIGHT},
-    resources::{InGameMode, InGameSetting},
+    constants::GgrsConfig,
+    resources::{
+        replay::{ReplayPlayback, ReplayRecorder},
+        touch_zones::{TouchZones, VirtualButtonsConfig},
+        AppConfig, AppConfigAssets, InGameMode, InGameSetting,
+    },
+    utils::input_utils::LocalInput,
 };
 
-pub fn userinput_system(
-    keys: Res<Input<KeyCode>>,
-    touches: Res<Touches>,
-    primary_query: Query<&Window, With<PrimaryWindow>>,
-    mut userinput_query: Query<&mut Userinput>,
-) {
-    let mut accelection = Vec2::ZERO;
-    if keys.any_pressed([KeyCode::Right, KeyCode::D]) {
-        accelection.x = 1.;
-    }
-    if keys.any_pressed([KeyCode::Left, KeyCode::A]) {
-        accelection.x = -1.;
-    }
-
-    let maybe_touch = touches.iter().last();
-    if let Ok(window) = primary_query.get_single() {
-        let center = window.width() / 2.0;
-        if let Some(touch) = maybe_touch {
-            if touches.get_pressed(touch.id()).is_some() {
-                let tx = touch.position().x;
-                if tx > center {
-                    accelection.x = 1.;
-                } else if tx < center {
-                    accelection.x = -1.;
-                }
-            }
-        }
-    }
-
-    for mut userinput in userinput_query.iter_mut() {
-        userinput.move_accelection = accelection;
-    }
-}
-
 pub fn userinput_system_2(
     in_game_setting: Res<InGameSetting>,
-
-    keys: Res<Input<KeyCode>>,
-    touches: Res<Touches>,
-    primary_query: Query<&Window, With<PrimaryWindow>>,
-
+    local_input: LocalInput,
     maybe_network_inputs: Option<Res<PlayerInputs<GgrsConfig>>>,
+    maybe_playback: Option<ResMut<ReplayPlayback>>,
+    maybe_recorder: Option<ResMut<ReplayRecorder>>,
     mut player_query: Query<(&Player, &mut Userinput)>,
 ) {
     match in_game_setting.mode {
         InGameMode::Offline => {
-            let mut accelection = Vec2::ZERO;
-            if keys.any_pressed([KeyCode::Right, KeyCode::D]) {
-                accelection.x = 1.;
-            }
-            if keys.any_pressed([KeyCode::Left, KeyCode::A]) {
-                accelection.x = -1.;
-            }
-
-            let maybe_touch = touches.iter().last();
-            if let Ok(window) = primary_query.get_single() {
-                let center = window.width() / 2.0;
-                if let Some(touch) = maybe_touch {
-                    if touches.get_pressed(touch.id()).is_some() {
-                        let tx = touch.position().x;
-                        if tx > center {
-                            accelection.x = 1.;
-                        } else if tx < center {
-                            accelection.x = -1.;
-                        }
-                    }
-                }
+            // every player is local offline, unless a replay is playing
+            let inputs = match maybe_playback {
+                Some(mut playback) => playback.next_inputs(),
+                None => (0..in_game_setting.num_players)
+                    .map(|handle| local_input.read(handle))
+                    .collect(),
+            };
+            for (player, mut userinput) in player_query.iter_mut() {
+                let input = inputs.get(player.handle).copied().unwrap_or_default();
+                userinput.move_accelection = input.move_accelection();
             }
-
-            for (_, mut userinput) in player_query.iter_mut() {
-                userinput.move_accelection = accelection;
+            if let Some(mut recorder) = maybe_recorder {
+                recorder.push(&inputs);
             }
         }
         InGameMode::Online => {
             if let Some(network_inputs) = maybe_network_inputs {
                 for (player, mut userinput) in player_query.iter_mut() {
                     let (input, _) = network_inputs[player.handle];
-
-                    let mut accelection = Vec2::ZERO;
-                    if input & INPUT_RIGHT != 0 {
-                        accelection.x += 1.;
-                    }
-                    if input & INPUT_LEFT != 0 {
-                        accelection.x -= 1.;
-                    }
-
-                    userinput.move_accelection = accelection;
+                    userinput.move_accelection = input.move_accelection();
                 }
             }
         }
         _ => {}
     }
 }
+
+pub fn init_touch_config(
+    mut commands: Commands,
+    app_config_assets: Res<AppConfigAssets>,
+    assets: Res<Assets<AppConfig>>,
+) {
+    let (touch_zones, virtual_buttons) = match assets.get(&app_config_assets.main) {
+        Some(config) => (config.touch.clone(), config.virtual_buttons),
+        None => (TouchZones::default(), VirtualButtonsConfig::default()),
+    };
+    commands.insert_resource(touch_zones);
+    commands.insert_resource(virtual_buttons);
+}
@@ -1 +1,4 @@
+pub mod input_utils;
+pub mod network_simulator;
+pub mod network_utils;
 pub mod physis_utils;
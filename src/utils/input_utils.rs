@@ -0,0 +1,186 @@
+use bevy::{ecs::system::SystemParam, prelude::*, window::PrimaryWindow};
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{GAMEPAD_DEAD_ZONE, INPUT_AXIS_MAX, TOUCH_DRAG_DEAD_ZONE, TOUCH_DRAG_RANGE},
+    resources::{
+        gamepads::LocalGamepads,
+        key_bindings::KeyBindings,
+        touch_zones::{TouchZones, VirtualButtonsConfig},
+        InGameSetting,
+    },
+};
+
+// the input sent to ggrs every frame, also used as is offline
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Pod, Zeroable, Serialize, Deserialize)]
+pub struct PlayerInput {
+    // horizontal move axis, -INPUT_AXIS_MAX is full left
+    pub move_x: i8,
+    // a bitmask kept for future actions, no binding sets a bit yet
+    pub buttons: u8,
+}
+
+impl PlayerInput {
+    pub fn new(move_x: f32, buttons: u8) -> Self {
+        let move_x = (move_x.clamp(-1.0, 1.0) * INPUT_AXIS_MAX as f32).round() as i8;
+        Self { move_x, buttons }
+    }
+
+    pub fn move_axis(&self) -> f32 {
+        self.move_x as f32 / INPUT_AXIS_MAX as f32
+    }
+
+    pub fn move_accelection(&self) -> Vec2 {
+        Vec2::new(self.move_axis(), 0.0)
+    }
+}
+
+// bevy_winit 0.10 passes touches on as winit reports them on desktop, web and mobile
+// alike, y growing down from the top of the window (only the cursor gets flipped), the
+// touch zones measure y up from the bottom so every touch goes through here first
+pub fn touch_position(position: Vec2, window: &Window) -> Vec2 {
+    Vec2::new(position.x, window.height() - position.y)
+}
+
+// maps keyboard, gamepad and touch to a `PlayerInput`, offline it's applied directly,
+// online it's what the ggrs input system sends
+#[derive(SystemParam)]
+pub struct LocalInput<'w, 's> {
+    keys: Res<'w, Input<KeyCode>>,
+    key_bindings: Res<'w, KeyBindings>,
+    in_game_setting: Res<'w, InGameSetting>,
+    local_gamepads: Res<'w, LocalGamepads>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+    gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+    touches: Res<'w, Touches>,
+    touch_zones: Res<'w, TouchZones>,
+    virtual_buttons: Res<'w, VirtualButtonsConfig>,
+    primary_query: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+}
+
+impl<'w, 's> LocalInput<'w, 's> {
+    // `local_player` is 0 for the first player on this device
+    pub fn read(&self, local_player: usize) -> PlayerInput {
+        let num_local_players = self.in_game_setting.num_local_players().max(1);
+        let mut move_x = 0.0;
+
+        if let Some(bindings) = self.key_bindings.player(local_player) {
+            // a key shared with a later player on this device is theirs, e.g. arrows in local 2P
+            let others: Vec<KeyCode> = (local_player + 1..num_local_players)
+                .filter_map(|x| self.key_bindings.player(x))
+                .flat_map(|x| x.move_left.iter().chain(x.move_right.iter()).copied())
+                .collect();
+            let pressed = |keys: &[KeyCode]| {
+                keys.iter()
+                    .any(|key| !others.contains(key) && self.keys.pressed(*key))
+            };
+            if pressed(&bindings.move_left) {
+                move_x -= 1.0;
+            }
+            if pressed(&bindings.move_right) {
+                move_x += 1.0;
+            }
+        }
+
+        let maybe_gamepad = self.local_gamepads.gamepad(local_player, num_local_players);
+        if let Some(gamepad) = maybe_gamepad {
+            let pressed = |button_type| {
+                self.gamepad_buttons
+                    .pressed(GamepadButton::new(gamepad, button_type))
+            };
+            if pressed(GamepadButtonType::DPadLeft) {
+                move_x = -1.0;
+            }
+            if pressed(GamepadButtonType::DPadRight) {
+                move_x = 1.0;
+            }
+            let axis = GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX);
+            if let Some(x) = self.gamepad_axes.get(axis) {
+                if x.abs() > GAMEPAD_DEAD_ZONE {
+                    move_x = x;
+                }
+            }
+        }
+
+        if let Some(x) = self.touch_move_x(local_player, num_local_players) {
+            move_x = x;
+        }
+
+        if local_player == 0 {
+            let x = self.virtual_buttons_move_x();
+            if x != 0.0 {
+                move_x = x;
+            }
+        }
+
+        PlayerInput::new(move_x, 0)
+    }
+
+    // the on-screen buttons are hit-tested here instead of in their ui system, so the input
+    // sampled for a tick sees the same touches as the touch zones
+    fn virtual_buttons_move_x(&self) -> f32 {
+        let Ok(window) = self.primary_query.get_single() else {
+            return 0.0;
+        };
+        let window_size = Vec2::new(window.width(), window.height());
+        self.touches
+            .iter()
+            .filter_map(|touch| {
+                self.virtual_button_at(touch_position(touch.position(), window), window_size)
+            })
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+
+    // only shown to a lone local player
+    fn virtual_button_at(&self, position: Vec2, window_size: Vec2) -> Option<f32> {
+        if !self.virtual_buttons.enabled || self.in_game_setting.num_local_players() != 1 {
+            return None;
+        }
+        self.virtual_buttons.button_at(position, window_size)
+    }
+
+    // every finger that went down in one of the player's zones counts, so holding
+    // both sides cancels out like the keyboard
+    fn touch_move_x(&self, local_player: usize, num_local_players: usize) -> Option<f32> {
+        let window = self.primary_query.get_single().ok()?;
+        let window_size = Vec2::new(window.width(), window.height());
+        let zones = self.touch_zones.zones(num_local_players);
+
+        let mut result = None;
+        for touch in self.touches.iter() {
+            // fingers that went down on a button don't count for the zones
+            let start_position = touch_position(touch.start_position(), window);
+            if self
+                .virtual_button_at(start_position, window_size)
+                .is_some()
+            {
+                continue;
+            }
+            // zones are picked where the finger went down so dragging out of them is fine
+            let Some(zone) = zones.iter().find(|zone| {
+                zone.local_player == local_player && zone.contains(start_position, window_size)
+            }) else {
+                continue;
+            };
+
+            let tx = touch.position().x;
+            let drag = tx - touch.start_position().x;
+            let center = zone.center_x(window_size);
+            let x = if drag.abs() > TOUCH_DRAG_DEAD_ZONE {
+                drag / TOUCH_DRAG_RANGE
+            } else if tx > center {
+                1.0
+            } else if tx < center {
+                -1.0
+            } else {
+                0.0
+            };
+            let x = if zone.rotated { -x } else { x };
+            result = Some((result.unwrap_or(0.0) + x).clamp(-1.0, 1.0));
+        }
+        result
+    }
+}
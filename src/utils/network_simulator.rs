@@ -0,0 +1,88 @@
+use std::{sync::Mutex, time::Duration};
+
+use bevy::utils::Instant;
+use bevy_ggrs::ggrs::{Message, NonBlockingSocket};
+use serde::Deserialize;
+
+use crate::utils::network_utils::NetAddress;
+
+// extra delay for a reordered packet, so the packets sent after it arrive first
+const REORDER_DELAY_MS: u64 = 50;
+
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(default)]
+pub struct NetworkSimulatorConfig {
+    pub enabled: bool,
+    pub latency_ms: u64,
+    pub jitter_ms: u64,
+    // chances between 0 and 1
+    pub packet_loss: f32,
+    pub reorder: f32,
+    // the same seed drops and delays the same packets every run
+    pub seed: u64,
+}
+
+struct DelayedMessage {
+    deliver_at: Instant,
+    addr: NetAddress,
+    msg: Message,
+}
+
+// delays, drops and reorders outgoing packets before they reach the real socket. due
+// packets go out on the next send or receive, ggrs polls for received messages every
+// frame so the delay is at most a frame longer than configured
+pub struct SimulatedSocket<S> {
+    inner: S,
+    config: NetworkSimulatorConfig,
+    // ggrs sockets have to be `Sync`, the rng alone is not
+    rng: Mutex<fastrand::Rng>,
+    outgoing: Vec<DelayedMessage>,
+}
+
+impl<S: NonBlockingSocket<NetAddress>> SimulatedSocket<S> {
+    pub fn new(inner: S, config: NetworkSimulatorConfig) -> Self {
+        Self {
+            inner,
+            config,
+            rng: Mutex::new(fastrand::Rng::with_seed(config.seed)),
+            outgoing: Vec::new(),
+        }
+    }
+
+    fn flush(&mut self) {
+        let now = Instant::now();
+        self.outgoing.sort_by_key(|x| x.deliver_at);
+        let num_due = self.outgoing.partition_point(|x| x.deliver_at <= now);
+        for delayed in self.outgoing.drain(..num_due) {
+            self.inner.send_to(&delayed.msg, &delayed.addr);
+        }
+    }
+}
+
+impl<S: NonBlockingSocket<NetAddress>> NonBlockingSocket<NetAddress> for SimulatedSocket<S> {
+    fn send_to(&mut self, msg: &Message, addr: &NetAddress) {
+        if !self.config.enabled {
+            self.inner.send_to(msg, addr);
+            return;
+        }
+        let rng = self.rng.get_mut().unwrap();
+        if rng.f32() < self.config.packet_loss {
+            return;
+        }
+        let mut delay_ms = self.config.latency_ms + rng.u64(0..=self.config.jitter_ms);
+        if rng.f32() < self.config.reorder {
+            delay_ms += REORDER_DELAY_MS;
+        }
+        self.outgoing.push(DelayedMessage {
+            deliver_at: Instant::now() + Duration::from_millis(delay_ms),
+            addr: *addr,
+            msg: msg.clone(),
+        });
+        self.flush();
+    }
+
+    fn receive_all_messages(&mut self) -> Vec<(NetAddress, Message)> {
+        self.flush();
+        self.inner.receive_all_messages()
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    io,
+    net::{SocketAddr, UdpSocket},
+    sync::{Arc, Mutex},
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ggrs::ggrs::{Message, NonBlockingSocket};
+use bevy_matchbox::matchbox_socket::WebRtcChannel;
+use bevy_matchbox::prelude::{MultipleChannels, PeerId};
+use bevy_matchbox::MatchboxSocket;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{LAN_MAX_GGRS_BACKLOG, RELIABLE_CHANNEL},
+    resources::{matchmaking::MatchmakingRole, GameRules},
+};
+
+// ggrs address of a remote player, either a matchbox peer or a lan player
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum NetAddress {
+    Peer(PeerId),
+    Udp(SocketAddr),
+}
+
+// messages sent over the reliable channel, the ggrs channel is only used by the session
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum NetMessage {
+    Role(MatchmakingRole),
+    Nickname(String),
+    Ready(bool),
+    // the host started or cancelled the countdown
+    Countdown(bool),
+    Rematch {
+        seed: u64,
+    },
+    LanAnnounce {
+        num_players: usize,
+        num_joined: usize,
+        rules: GameRules,
+    },
+    LanJoin,
+    // `None` is the host, who sends this message
+    LanStart {
+        handle: usize,
+        players: Vec<Option<SocketAddr>>,
+        rules: GameRules,
+    },
+    LanStartAck,
+    // the host gave up on a joiner that never acknowledged the start
+    LanCancel,
+    // the host going in-game, sent to the other players and the spectators it added
+    Start {
+        players: Vec<PeerId>,
+    },
+    // the answer to a peer that is left out of a match or connects after it started
+    MatchStarted,
+}
+
+// the socket used by the ggrs session, shared so a rematch can start a new session on it
+#[derive(Resource, Clone)]
+pub enum GgrsChannel {
+    Matchbox(Arc<Mutex<WebRtcChannel>>),
+    Lan(LanSocket),
+}
+
+impl GgrsChannel {
+    pub fn new(channel: WebRtcChannel) -> Self {
+        Self::Matchbox(Arc::new(Mutex::new(channel)))
+    }
+}
+
+impl NonBlockingSocket<NetAddress> for GgrsChannel {
+    fn send_to(&mut self, msg: &Message, addr: &NetAddress) {
+        match (self, addr) {
+            (GgrsChannel::Matchbox(channel), NetAddress::Peer(peer)) => {
+                channel.lock().unwrap().send_to(msg, peer);
+            }
+            (GgrsChannel::Lan(socket), NetAddress::Udp(addr)) => {
+                socket.send_packet(LAN_GGRS_PACKET, msg, *addr);
+            }
+            (_, addr) => warn!("{:?} is not reachable from this socket", addr),
+        }
+    }
+
+    fn receive_all_messages(&mut self) -> Vec<(NetAddress, Message)> {
+        match self {
+            GgrsChannel::Matchbox(channel) => channel
+                .lock()
+                .unwrap()
+                .receive_all_messages()
+                .into_iter()
+                .map(|(peer, msg)| (NetAddress::Peer(peer), msg))
+                .collect(),
+            GgrsChannel::Lan(socket) => {
+                let mut inner = socket.0.lock().unwrap();
+                inner.poll();
+                inner
+                    .ggrs_messages
+                    .drain(..)
+                    .map(|(addr, msg)| (NetAddress::Udp(addr), msg))
+                    .collect()
+            }
+        }
+    }
+}
+
+const LAN_GGRS_PACKET: u8 = 0;
+const LAN_NET_PACKET: u8 = 1;
+const LAN_RECV_BUFFER_SIZE: usize = 4096;
+
+struct LanSocketInner {
+    socket: UdpSocket,
+    buffer: Vec<u8>,
+    ggrs_messages: Vec<(SocketAddr, Message)>,
+    // ggrs packets are dropped until a session uses the socket
+    ggrs_open: bool,
+    net_messages: Vec<(SocketAddr, NetMessage)>,
+}
+
+impl LanSocketInner {
+    // the first byte of every packet tells if it belongs to ggrs or to us
+    fn poll(&mut self) {
+        loop {
+            let (len, addr) = match self.socket.recv_from(&mut self.buffer) {
+                Ok(x) => x,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return,
+                // windows reports unreachable peers as errors on the next receive
+                Err(err) if err.kind() == io::ErrorKind::ConnectionReset => continue,
+                Err(err) => {
+                    error!("lan socket error: {}", err);
+                    return;
+                }
+            };
+            let Some((tag, payload)) = self.buffer[..len].split_first() else {
+                continue;
+            };
+            match *tag {
+                LAN_GGRS_PACKET if !self.ggrs_open => {}
+                LAN_GGRS_PACKET if self.ggrs_messages.len() >= LAN_MAX_GGRS_BACKLOG => {
+                    warn!("dropped ggrs packet from {}, nothing reads them", addr);
+                }
+                LAN_GGRS_PACKET => match bincode::deserialize(payload) {
+                    Ok(msg) => self.ggrs_messages.push((addr, msg)),
+                    Err(err) => warn!("dropped malformed ggrs packet from {}: {}", addr, err),
+                },
+                LAN_NET_PACKET => match bincode::deserialize(payload) {
+                    Ok(message) => self.net_messages.push((addr, message)),
+                    Err(err) => warn!("dropped malformed message from {}: {}", addr, err),
+                },
+                _ => warn!("dropped unknown packet from {}", addr),
+            }
+        }
+    }
+}
+
+// one udp port carries both the lobby messages and the ggrs packets, instead of ggrs'
+// UdpNonBlockingSocket, so a host only has to open one port and rematches reuse it
+#[derive(Resource, Clone)]
+pub struct LanSocket(Arc<Mutex<LanSocketInner>>);
+
+impl LanSocket {
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_nonblocking(true)?;
+        socket.set_broadcast(true)?;
+        Ok(Self(Arc::new(Mutex::new(LanSocketInner {
+            socket,
+            buffer: vec![0; LAN_RECV_BUFFER_SIZE],
+            ggrs_messages: Vec::new(),
+            ggrs_open: false,
+            net_messages: Vec::new(),
+        }))))
+    }
+
+    pub fn open_ggrs(&self) {
+        self.0.lock().unwrap().ggrs_open = true;
+    }
+
+    pub fn send_message(&self, addr: SocketAddr, message: &NetMessage) {
+        self.send_packet(LAN_NET_PACKET, message, addr);
+    }
+
+    pub fn receive_messages(&self) -> Vec<(SocketAddr, NetMessage)> {
+        let mut inner = self.0.lock().unwrap();
+        inner.poll();
+        inner.net_messages.drain(..).collect()
+    }
+
+    fn send_packet(&self, tag: u8, payload: &impl Serialize, addr: SocketAddr) {
+        let mut packet = vec![tag];
+        if let Err(err) = bincode::serialize_into(&mut packet, payload) {
+            error!("failed to serialize packet: {}", err);
+            return;
+        }
+        let inner = self.0.lock().unwrap();
+        if let Err(err) = inner.socket.send_to(&packet, addr) {
+            warn!("failed to send packet to {}: {}", addr, err);
+        }
+    }
+}
+
+// sends messages to remote players no matter which socket they are connected with
+#[derive(SystemParam)]
+pub struct NetMessenger<'w> {
+    matchbox_socket: Option<ResMut<'w, MatchboxSocket<MultipleChannels>>>,
+    lan_socket: Option<Res<'w, LanSocket>>,
+}
+
+impl NetMessenger<'_> {
+    pub fn send(&mut self, addr: &NetAddress, message: &NetMessage) {
+        match addr {
+            NetAddress::Peer(peer) => {
+                if let Some(socket) = self.matchbox_socket.as_mut() {
+                    send_message(socket, *peer, message);
+                }
+            }
+            NetAddress::Udp(addr) => {
+                if let Some(socket) = &self.lan_socket {
+                    socket.send_message(*addr, message);
+                }
+            }
+        }
+    }
+
+    pub fn receive(&mut self) -> Vec<(NetAddress, NetMessage)> {
+        let mut messages = Vec::new();
+        if let Some(socket) = self.matchbox_socket.as_mut() {
+            messages.extend(
+                receive_messages(socket)
+                    .into_iter()
+                    .map(|(peer, message)| (NetAddress::Peer(peer), message)),
+            );
+        }
+        if let Some(socket) = &self.lan_socket {
+            messages.extend(
+                socket
+                    .receive_messages()
+                    .into_iter()
+                    .map(|(addr, message)| (NetAddress::Udp(addr), message)),
+            );
+        }
+        messages
+    }
+}
+
+pub fn send_message(
+    socket: &mut MatchboxSocket<MultipleChannels>,
+    peer: PeerId,
+    message: &NetMessage,
+) {
+    match bincode::serialize(message) {
+        Ok(packet) => socket
+            .channel(RELIABLE_CHANNEL)
+            .send(packet.into_boxed_slice(), peer),
+        Err(err) => error!("failed to serialize {:?}: {}", message, err),
+    }
+}
+
+pub fn receive_messages(
+    socket: &mut MatchboxSocket<MultipleChannels>,
+) -> Vec<(PeerId, NetMessage)> {
+    socket
+        .channel(RELIABLE_CHANNEL)
+        .receive()
+        .into_iter()
+        .filter_map(|(peer, packet)| match bincode::deserialize(&packet) {
+            Ok(message) => Some((peer, message)),
+            Err(err) => {
+                warn!("dropped malformed message from {:?}: {}", peer, err);
+                None
+            }
+        })
+        .collect()
+}
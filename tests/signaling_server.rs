@@ -0,0 +1,102 @@
+#![cfg(feature = "signaling_server")]
+
+use std::{
+    io::{BufRead, BufReader},
+    process::{Child, ChildStdout, Command, Stdio},
+    time::Duration,
+};
+
+use futures_util::StreamExt;
+use tokio::{net::TcpStream, time::timeout};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+struct Server {
+    child: Child,
+    // kept open, the server logs every join and would fail writing to a closed pipe
+    _stdout: BufReader<ChildStdout>,
+    addr: String,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+// port 0 lets the os pick a free port, the server prints the one it got
+fn start_server() -> Server {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_signaling_server"))
+        .arg("127.0.0.1:0")
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to start the signaling server");
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    let addr = line
+        .trim()
+        .strip_prefix("signaling server listening on ")
+        .unwrap_or_else(|| panic!("unexpected output: {}", line))
+        .to_string();
+    Server {
+        child,
+        _stdout: stdout,
+        addr,
+    }
+}
+
+async fn connect(server: &Server, room: &str) -> Client {
+    let url = format!("ws://{}/{}", server.addr, room);
+    let (client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    client
+}
+
+// the next event as e.g. ("NewPeer", "<uuid>")
+async fn next_event(client: &mut Client) -> (String, String) {
+    loop {
+        let message = timeout(Duration::from_secs(5), client.next())
+            .await
+            .expect("no event from the server")
+            .expect("server closed the connection")
+            .unwrap();
+        let Message::Text(text) = message else {
+            continue;
+        };
+        let event: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let (kind, value) = event.as_object().unwrap().iter().next().unwrap();
+        return (kind.clone(), value.as_str().unwrap_or_default().to_string());
+    }
+}
+
+#[tokio::test]
+async fn quick_match_pairs_peers_and_relays_leaving() {
+    let server = start_server();
+
+    let mut first = connect(&server, "quick_match?next=2").await;
+    let (kind, first_id) = next_event(&mut first).await;
+    assert_eq!(kind, "IdAssigned");
+
+    let mut second = connect(&server, "quick_match?next=2").await;
+    let (kind, second_id) = next_event(&mut second).await;
+    assert_eq!(kind, "IdAssigned");
+    assert_ne!(first_id, second_id);
+
+    // the peer already in the room hears about the new one and starts the handshake
+    assert_eq!(
+        next_event(&mut first).await,
+        ("NewPeer".to_string(), second_id.clone())
+    );
+
+    // the full pair left the waiting room, a third peer starts a new match
+    let mut third = connect(&server, "quick_match?next=2").await;
+    assert_eq!(next_event(&mut third).await.0, "IdAssigned");
+
+    // but the pair still hears when one of them leaves
+    second.close(None).await.unwrap();
+    assert_eq!(
+        next_event(&mut first).await,
+        ("PeerLeft".to_string(), second_id)
+    );
+}